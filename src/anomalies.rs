@@ -1,19 +1,8 @@
-use std::ops::Range;
+use crate::FileSpan;
 
 /// Enum representing every warning that can be reported from Ruxnasm.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Warning {
-    /// This warnings gets reported when a token is longer than 64 characters and must be cut off.
-    ///
-    /// # Example
-    ///
-    /// ```uxntal
-    /// @a-really-long-label-name-like-seriously-this-is-so-long-why-would-anyone-do-this
-    /// ```
-    TokenTrimmed {
-        /// Span of the cut off part of the token.
-        span: Range<usize>,
-    },
     /// This warning gets reported when an instruction mode is defined multiple times for a
     /// single instruction, which is valid, but unnecessary.
     ///
@@ -28,9 +17,9 @@ pub enum Warning {
         /// The whole instruction.
         instruction: String,
         /// Span of the unnecessary instruction mode character.
-        span: Range<usize>,
+        span: FileSpan,
         /// Span of the instruction mode character defined for the first time.
-        other_span: Range<usize>,
+        other_span: FileSpan,
     },
     /// This warning gets reported when a macro is never used.
     ///
@@ -43,7 +32,7 @@ pub enum Warning {
         /// Name of the unused macro.
         name: String,
         /// Span of the macro definition.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This warning gets reported when a label is never used.
     ///
@@ -56,7 +45,40 @@ pub enum Warning {
         /// Name of the unused label.
         name: String,
         /// Span of the label definition.
-        span: Range<usize>,
+        span: FileSpan,
+    },
+    /// This warning gets reported when two labels have different names, but their first
+    /// 64 characters are the same. Uxnasm cuts the names off after 64 characters, so the
+    /// labels would collide there.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// @a-really-long-label-name-like-seriously-this-is-so-long-why-would-anyone-do-this
+    /// @a-really-long-label-name-like-seriously-this-is-so-long-why-would-anyone-do-that
+    /// ```
+    LabelCollidesWhenTrimmed {
+        /// Name of the label.
+        name: String,
+        /// Name of the label it collides with.
+        other_name: String,
+        /// Span of the label definition.
+        span: FileSpan,
+        /// Span of the definition of the label it collides with.
+        other_span: FileSpan,
+    },
+    /// This warning gets reported by a warning directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^warning "this is deprecated"
+    /// ```
+    Custom {
+        /// The message of the warning.
+        message: String,
+        /// Span of the directive.
+        span: FileSpan,
     },
 }
 
@@ -73,7 +95,7 @@ pub enum Error {
     /// ```
     NoMatchingClosingParenthesis {
         /// Span of the opening parenthesis with no matching closing parenthesis.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when a closing parenthesis has no matching opening
     /// parenthesis.
@@ -85,7 +107,7 @@ pub enum Error {
     /// ```
     NoMatchingOpeningParenthesis {
         /// Span of the closing parenthesis with no matching opening parenthesis.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is no macro name after a macro definition
     /// rune.
@@ -97,7 +119,7 @@ pub enum Error {
     /// ```
     MacroNameExpected {
         /// Span of the macro definition rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is no label name after a label definition
     /// rune.
@@ -109,7 +131,7 @@ pub enum Error {
     /// ```
     LabelExpected {
         /// Span of the label definition rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is no sublabel name after a sublabel
     /// definition rune.
@@ -121,7 +143,7 @@ pub enum Error {
     /// ```
     SublabelExpected {
         /// Span of the sublabel definition rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when a label or a sublabel name contains a slash
     /// character.
@@ -133,19 +155,7 @@ pub enum Error {
     /// ```
     SlashInLabelOrSublabel {
         /// Span of the slash in the label of sublabel.
-        span: Range<usize>,
-    },
-    /// This error gets reported when an identifier contains more than one slash
-    /// character.
-    ///
-    /// # Example
-    ///
-    /// ```uxntal
-    /// .label-name/sublabel/name
-    /// ```
-    MoreThanOneSlashInIdentifier {
-        /// Span of the slash in the identifier.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when a label name in a label definition has an
     /// ampersand as the first character.
@@ -157,7 +167,7 @@ pub enum Error {
     /// ```
     AmpersandAtTheStartOfLabel {
         /// Span of the ampersand at the start of the label.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is no identifier after an address rune
     /// (literal zero-page address rune, literal relative address rune, literal
@@ -170,7 +180,7 @@ pub enum Error {
     /// ```
     IdentifierExpected {
         /// Span of the address rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is no hexadecimal number after an
     /// absolute or relative pad rune.
@@ -182,7 +192,7 @@ pub enum Error {
     /// ```
     HexNumberExpected {
         /// Span of the abolute or relative pad rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is no character or hexadecimal number
     /// after a literal hex rune.
@@ -194,7 +204,7 @@ pub enum Error {
     /// ```
     HexNumberOrCharacterExpected {
         /// Span of the literal hex rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is no character after a character rune.
     ///
@@ -205,7 +215,7 @@ pub enum Error {
     /// ```
     CharacterExpected {
         /// Span of the character rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when there is more than one byte after a character
     /// rune.
@@ -219,7 +229,7 @@ pub enum Error {
         /// Sequence of bytes after the character rune.
         bytes: Vec<u8>,
         /// Span of the characters after the character rune.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when a hexadecimal number contains an invalid
     /// digit.
@@ -235,7 +245,67 @@ pub enum Error {
         /// The whole hexadecimal number with the invalid digit.
         number: String,
         /// Span of the hexadecimal number.
-        span: Range<usize>,
+        span: FileSpan,
+    },
+    /// This error gets reported when a decimal number contains an invalid digit.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #-12a
+    /// ```
+    DecimalDigitInvalid {
+        /// The invalid digit.
+        digit: char,
+        /// The whole number with the invalid digit.
+        number: String,
+        /// Span of the invalid digit.
+        span: FileSpan,
+    },
+    /// This error gets reported when a binary number contains an invalid digit.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #%0120:s
+    /// ```
+    BinaryDigitInvalid {
+        /// The invalid digit.
+        digit: char,
+        /// The whole number with the invalid digit.
+        number: String,
+        /// Span of the invalid digit.
+        span: FileSpan,
+    },
+    /// This error gets reported when a number after a literal hex rune has a base
+    /// prefix or a size suffix, but no digits.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #+_:s
+    /// ```
+    DigitExpected {
+        /// Span of the number.
+        span: FileSpan,
+    },
+    /// This error gets reported when a number after a literal hex rune does not
+    /// fit in its size.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// #+300:b
+    /// ```
+    NumberOutOfRange {
+        /// The number.
+        number: String,
+        /// The smallest value that fits in the size of the number.
+        minimum: i32,
+        /// The largest value that fits in the size of the number.
+        maximum: i32,
+        /// Span of the number.
+        span: FileSpan,
     },
     /// This error gets reported when a hexadecimal number after a literal hex
     /// rune has a length of 3, i.e. it is made out of exactly 3 hexadecimal digits.
@@ -251,7 +321,7 @@ pub enum Error {
         /// The hexadecimal number.
         number: String,
         /// Span of the hexadecimal number.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when the hexadecimal number after a literal hex
     /// rune is longer than 4 hexadecimal digits.
@@ -267,7 +337,7 @@ pub enum Error {
         /// The hexadecimal number.
         number: String,
         /// Span of the hexadecimal number.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when the macro name after a macro definition
     /// rune is a valid hexadecimal number i.e. it contains exactly 2 or 4 valid
@@ -282,7 +352,7 @@ pub enum Error {
         /// The hexadecimal number that was meant to be a macro name.
         number: String,
         /// Span of the hexadecimal number that was meant to be a macro name.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when the macro name after a macro definition
     /// rune is a valid instruction.
@@ -296,10 +366,10 @@ pub enum Error {
         /// The instruction that was meant to be a macro name.
         instruction: String,
         /// Span of the instruction that was meant to be a macro name.
-        span: Range<usize>,
+        span: FileSpan,
     },
-    /// This error gets reported during an attempt to expand a macro that has not
-    /// been previously defined.
+    /// This error gets reported when a bare word is neither the name of a
+    /// previously defined macro, nor the name of a label that could be called.
     ///
     /// # Example
     ///
@@ -309,8 +379,8 @@ pub enum Error {
     MacroUndefined {
         /// Name of the macro.
         name: String,
-        /// Span of the macro invocation.
-        span: Range<usize>,
+        /// Span of the bare word.
+        span: FileSpan,
     },
     /// This error gets reported when a macro with the same name is defined
     /// multiple times.
@@ -325,9 +395,9 @@ pub enum Error {
         /// Name of the macro.
         name: String,
         /// Span of the current macro definition.
-        span: Range<usize>,
+        span: FileSpan,
         /// Span of the previous macro definition.
-        other_span: Range<usize>,
+        other_span: FileSpan,
     },
     /// This error gets reported when a label with the same name is defined
     /// multiple times.
@@ -342,21 +412,9 @@ pub enum Error {
         /// Name of the label.
         name: String,
         /// Span of the current label definition.
-        span: Range<usize>,
+        span: FileSpan,
         /// Span of the previous label definition.
-        other_span: Range<usize>,
-    },
-    /// This error gets reported when an opening brace character is not directly
-    /// preceded by a macro definition.
-    ///
-    /// # Example
-    ///
-    /// ```uxntal
-    /// { ADD }
-    /// ```
-    OpeningBraceNotAfterMacroDefinition {
-        /// Span of the opening brace.
-        span: Range<usize>,
+        other_span: FileSpan,
     },
     /// This error gets reported when a closing brace has no matching opening
     /// brace.
@@ -368,7 +426,7 @@ pub enum Error {
     /// ```
     NoMatchingOpeningBrace {
         /// Span of the closing brace with no matching opening brace.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when an opening brace is not closed i.e. it has
     /// no matching closing brace.
@@ -380,21 +438,37 @@ pub enum Error {
     /// ```
     NoMatchingClosingBrace {
         /// Span of the opening brace with no matching closing brace.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported during an attempt to define a sublabel, when
-    /// no previous label has been defined.
+    /// no previous label has been defined, or, for a nested sublabel, when no
+    /// previous sublabel one level above it has been defined.
     ///
     /// # Example
     ///
     /// ```uxntal
     /// &sublabel
+    /// @label &&nested-sublabel
     /// ```
     SublabelDefinedWithoutScope {
         /// Name of the sublabel.
         name: String,
         /// Span of the sublabel definition.
-        span: Range<usize>,
+        span: FileSpan,
+    },
+    /// This error gets reported during an attempt to define a local label
+    /// outside of a macro.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// @%loop
+    /// ```
+    LocalLabelDefinedOutsideMacro {
+        /// Name of the local label.
+        name: String,
+        /// Span of the local label definition.
+        span: FileSpan,
     },
     /// This error gets reported when a closing bracket has no matching opening
     /// bracket.
@@ -406,7 +480,7 @@ pub enum Error {
     /// ```
     NoMatchingOpeningBracket {
         /// Span of the closing bracket with no matching opening bracket.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported when an opening bracket is not closed i.e. it has
     /// no matching closing bracket.
@@ -418,7 +492,7 @@ pub enum Error {
     /// ```
     NoMatchingClosingBracket {
         /// Span of the opening bracket with no matching closing bracket.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error wraps an error that has been reported from a macro definition.
     ///
@@ -432,21 +506,37 @@ pub enum Error {
         /// The error that has been reported from a macro definition.
         original_error: Box<Error>,
         /// Span of the macro invocation.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported during an attempt to reference a sublabel, when
-    /// no previous label has been defined.
+    /// no previous label has been defined, or, for a nested sublabel, when no
+    /// previous sublabel one level above it has been defined.
     ///
     /// # Example
     ///
     /// ```uxntal
     /// .&sublabel
+    /// @label .&&nested-sublabel
     /// ```
     SublabelReferencedWithoutScope {
         /// Name of the sublabel.
         name: String,
         /// Span of the sublabel reference.
-        span: Range<usize>,
+        span: FileSpan,
+    },
+    /// This error gets reported during an attempt to reference a local label
+    /// outside of a macro.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ,%loop
+    /// ```
+    LocalLabelReferencedOutsideMacro {
+        /// Name of the local label.
+        name: String,
+        /// Span of the local label reference.
+        span: FileSpan,
     },
     /// This error gets reported during an attempt to reference a label that
     /// has not been defined.
@@ -460,7 +550,37 @@ pub enum Error {
         /// Name of the label.
         name: String,
         /// Span of the label reference.
-        span: Range<usize>,
+        span: FileSpan,
+    },
+    /// This error gets reported during an attempt to reference a numeric label
+    /// backward, when no numeric label with that name has been defined before the
+    /// reference.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ,1b JMP &1
+    /// ```
+    NoPrecedingNumericLabel {
+        /// Name of the numeric label, without the direction suffix.
+        name: String,
+        /// Span of the numeric label reference.
+        span: FileSpan,
+    },
+    /// This error gets reported during an attempt to reference a numeric label
+    /// forward, when no numeric label with that name is defined after the
+    /// reference.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// &1 ,1f JMP
+    /// ```
+    NoFollowingNumericLabel {
+        /// Name of the numeric label, without the direction suffix.
+        name: String,
+        /// Span of the numeric label reference.
+        span: FileSpan,
     },
     /// This error gets reported during an attempt to reference a non-zero-page label
     /// after a literal zero-page address rune.
@@ -477,7 +597,7 @@ pub enum Error {
         /// Name of the identifier that is referenced by the literal zero-page address.
         identifier: String,
         /// Span of the literal zero-page address.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported during an attempt to reference a label that
     /// is too far to be a relative address after a literal relative address rune.
@@ -494,9 +614,9 @@ pub enum Error {
         /// Name of the identifier that is referenced by the literal relative address.
         identifier: String,
         /// Span of the literal relative address.
-        span: Range<usize>,
+        span: FileSpan,
         /// Span of the label definition that is referenced by the literal relative address.
-        other_span: Range<usize>,
+        other_span: FileSpan,
     },
     /// This error gets reported when there are bytes in the zeroth page (first
     /// 256 bytes) of the binary.
@@ -508,7 +628,7 @@ pub enum Error {
     /// ```
     BytesInZerothPage {
         /// Span of the tokens in the zeroth page.
-        span: Range<usize>,
+        span: FileSpan,
     },
     /// This error gets reported during an attempt to do an absolute pad
     /// to an address before the current address pointer.
@@ -525,7 +645,21 @@ pub enum Error {
         /// The address to which the absolute pad is attempted.
         desired_pointer: usize,
         /// Span of the absolute pad.
-        span: Range<usize>,
+        span: FileSpan,
+    },
+    /// This error gets reported when a pad refers to a label that is defined
+    /// after the pad. The address of the label would depend on the pad itself.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |label @label
+    /// ```
+    PadForwardReference {
+        /// The operand of the pad.
+        operand: String,
+        /// Span of the pad.
+        span: FileSpan,
     },
     /// This error gets reported when the program size exceeds 65536 bytes.
     ///
@@ -536,10 +670,658 @@ pub enum Error {
     /// ```
     ProgramTooLong {
         /// Span of the tokens that exceed the maximum size.
-        span: Range<usize>,
+        span: FileSpan,
     },
     RecursiveMacro {
-        chain: Vec<(String, Range<usize>)>,
-        span: Range<usize>,
+        chain: Vec<(String, FileSpan)>,
+        span: FileSpan,
+    },
+    /// This error gets reported when there is no file path after an include rune or
+    /// a binary include directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ~
+    /// ```
+    FilePathExpected {
+        /// Span of the include rune or the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when an included file cannot be found neither
+    /// relative to the including file, nor in any of the include paths.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ~does-not-exist.tal
+    /// ```
+    FileNotFound {
+        /// Path of the file, as written in the source.
+        path: String,
+        /// Span of the include.
+        span: FileSpan,
+    },
+    /// This error gets reported when an included file has been found, but cannot
+    /// be read.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ~a-directory.tal
+    /// ```
+    CouldNotReadFile {
+        /// Path of the file, as written in the source.
+        path: String,
+        /// Description of the underlying I/O error.
+        message: String,
+        /// Span of the include.
+        span: FileSpan,
+    },
+    /// This error gets reported when a file includes itself, either directly or
+    /// through other included files.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ( main.tal )
+    /// ~main.tal
+    /// ```
+    RecursiveInclude {
+        /// Path of the file, as written in the source.
+        path: String,
+        /// Span of the include that closes the cycle.
+        span: FileSpan,
+    },
+    /// This error gets reported when there is no parameter name after a colon in
+    /// a macro definition.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// %macro:a: { ;{a} }
+    /// ```
+    MacroParameterExpected {
+        /// Span of the colon.
+        span: FileSpan,
+    },
+    /// This error gets reported when a macro has multiple parameters with the
+    /// same name.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// %macro:a:a { ;{a} }
+    /// ```
+    MacroParameterDefinedMoreThanOnce {
+        /// Name of the parameter.
+        name: String,
+        /// Span of the current parameter definition.
+        span: FileSpan,
+        /// Span of the previous parameter definition.
+        other_span: FileSpan,
+    },
+    /// This error gets reported when there is no argument after a colon in a
+    /// macro invocation.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// %macro:a { ;{a} }
+    /// macro:
+    /// ```
+    MacroArgumentExpected {
+        /// Span of the colon.
+        span: FileSpan,
+    },
+    /// This error gets reported when a macro is invoked with a different number
+    /// of arguments than the number of its parameters.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// %macro:a:b { ;{a} ;{b} }
+    /// macro:label
+    /// ```
+    MacroArgumentCountMismatch {
+        /// Name of the macro.
+        name: String,
+        /// Number of the parameters of the macro.
+        expected: usize,
+        /// Number of the arguments in the macro invocation.
+        found: usize,
+        /// Span of the macro invocation.
+        span: FileSpan,
+        /// Span of the macro definition.
+        other_span: FileSpan,
+    },
+    /// This error gets reported when a macro parameter placeholder does not
+    /// name any parameter of the macro it is in, or is not in a macro at all.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// %macro:a { ;{b} }
+    /// macro:label
+    /// ```
+    MacroParameterUndefined {
+        /// Name of the parameter.
+        name: String,
+        /// Span of the placeholder.
+        span: FileSpan,
+    },
+    /// This error gets reported when there is no directive name after a
+    /// directive rune.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^
+    /// ```
+    DirectiveExpected {
+        /// Span of the directive rune.
+        span: FileSpan,
+    },
+    /// This error gets reported when the name after a directive rune is not the
+    /// name of any directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^directive
+    /// ```
+    DirectiveUnknown {
+        /// Name of the directive.
+        name: String,
+        /// Span of the directive name.
+        span: FileSpan,
+    },
+    /// This error gets reported when a conditional assembly directive is not
+    /// followed by its condition.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^ifdef #01
+    /// ```
+    ConditionExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when the value of a constant that has not been
+    /// defined is used.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^if DEBUG
+    /// ```
+    ConstantUndefined {
+        /// Name of the constant.
+        name: String,
+        /// Span of the constant reference.
+        span: FileSpan,
+    },
+    /// This error gets reported when an `^else` or an `^endif` directive has no
+    /// matching conditional assembly directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^endif
+    /// ```
+    NoMatchingIf {
+        /// Span of the directive with no matching conditional assembly directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when a conditional assembly directive has no
+    /// matching `^endif` directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^ifdef DEBUG
+    /// ```
+    NoMatchingEndif {
+        /// Span of the conditional assembly directive with no matching `^endif`
+        /// directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when a conditional assembly block has more than
+    /// one `^else` directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^ifdef DEBUG ^else ^else ^endif
+    /// ```
+    ElseDefinedMoreThanOnce {
+        /// Span of the current `^else` directive.
+        span: FileSpan,
+        /// Span of the previous `^else` directive.
+        other_span: FileSpan,
+    },
+    /// This error gets reported when a constant definition directive is not
+    /// followed by the name of the constant.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^const #18
+    /// ```
+    ConstantNameExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when the name of a constant in a constant
+    /// definition is not followed by a hexadecimal byte or short.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^const write ADD
+    /// ```
+    ConstantValueExpected {
+        /// Span of the name of the constant.
+        span: FileSpan,
+    },
+    /// This error gets reported when the value of a compile-time expression does not fit
+    /// in the operand it is used in.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 ;`ffff+2`
+    /// ```
+    ExpressionOverflow {
+        /// The value of the expression.
+        value: i64,
+        /// The largest value that the operand can hold.
+        maximum: u16,
+        /// Span of the operand containing the expression.
+        span: FileSpan,
+    },
+    /// This error gets reported when a compile-time expression divides by zero.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 ;`label/0` @label
+    /// ```
+    DivisionByZero {
+        /// Span of the divisor.
+        span: FileSpan,
+    },
+    /// This error gets reported when an operator in a compile-time expression is not
+    /// followed by an operand.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 ;`label+` @label
+    /// ```
+    OperandExpected {
+        /// Span of the character where the operand was expected.
+        span: FileSpan,
+    },
+    /// This error gets reported when an operand in a compile-time expression is not
+    /// followed by an operator.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 ;`label+2<3` @label
+    /// ```
+    OperatorExpected {
+        /// Span of the character where the operator was expected.
+        span: FileSpan,
+    },
+    /// This error gets reported when a compile-time expression is not followed
+    /// by a closing backtick.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 ;`label+2 @label
+    /// ```
+    ExpressionNotClosed {
+        /// Span of the operand containing the expression.
+        span: FileSpan,
+    },
+    /// This error gets reported when an alignment directive is not followed by
+    /// a hexadecimal byte or short.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^align page
+    /// ```
+    AlignmentExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when the alignment in an alignment directive is not
+    /// a power of two.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^align 03
+    /// ```
+    AlignmentNotPowerOfTwo {
+        /// The alignment.
+        alignment: u16,
+        /// Span of the alignment.
+        span: FileSpan,
+    },
+    /// This error gets reported when a fill directive is not followed by the number
+    /// of bytes to fill.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^fill
+    /// ```
+    FillLengthExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when the number of bytes in a fill directive is not
+    /// followed by a hexadecimal byte to fill them with.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^fill 10 ADD
+    /// ```
+    FillValueExpected {
+        /// Span of the number of bytes to fill.
+        span: FileSpan,
+    },
+    /// This error gets reported when the offset or the length of a binary include
+    /// reach past the end of the included file.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^incbin four-bytes.bin:0002:0010
+    /// ```
+    BinarySliceOutOfBounds {
+        /// Path of the file, as written in the source.
+        path: String,
+        /// Offset of the slice.
+        offset: usize,
+        /// Length of the slice, if given.
+        length: Option<usize>,
+        /// Length of the file.
+        file_length: usize,
+        /// Span of the binary include.
+        span: FileSpan,
+    },
+    /// This error gets reported when a quoted string contains an unknown or an
+    /// incomplete escape sequence.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// "Hello\q"
+    /// ```
+    EscapeSequenceInvalid {
+        /// The escape sequence, as written in the source.
+        sequence: String,
+        /// Span of the escape sequence.
+        span: FileSpan,
+    },
+    /// This error gets reported when a length-prefixed quoted string is longer than
+    /// 255 bytes.
+    StringTooLong {
+        /// Length of the string in bytes.
+        length: usize,
+        /// Span of the string.
+        span: FileSpan,
+    },
+    /// This error gets reported when a section directive is not followed by the
+    /// name of a section.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^section
+    /// ```
+    SectionNameExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported during an attempt to do an absolute pad in a
+    /// floating section, i.e. a section other than `code` and `zeropage`, which
+    /// gets placed only after the whole program is assembled.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^section data |8000
+    /// ```
+    AbsolutePadInFloatingSection {
+        /// Name of the section.
+        section: String,
+        /// Span of the absolute pad.
+        span: FileSpan,
+    },
+    /// This error gets reported when there are bytes in a section that only
+    /// reserves memory, i.e. in the `zeropage` or the `bss` section.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^section bss #01
+    /// ```
+    BytesInUninitializedSection {
+        /// Name of the section.
+        section: String,
+        /// Span of the bytes.
+        span: FileSpan,
+    },
+    /// This error gets reported when a zero-page allocation directive is not
+    /// followed by a label name.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^zp 02
+    /// ```
+    AllocationNameExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when the label name in a zero-page allocation
+    /// directive is not followed by a hexadecimal number of bytes to allocate.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^zp counter
+    /// ```
+    AllocationSizeExpected {
+        /// Span of the label name.
+        span: FileSpan,
+    },
+    /// This error gets reported when there are not enough bytes left in the zero
+    /// page for an allocation.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^zp buffer 0100
+    /// ^zp counter 01
+    /// ```
+    ZeroPageExhausted {
+        /// Name of the label.
+        name: String,
+        /// Number of bytes that could not be allocated.
+        size: u16,
+        /// Span of the allocation.
+        span: FileSpan,
+    },
+    /// This error gets reported when there is no condition after an assertion
+    /// directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^assert
+    /// ```
+    AssertionConditionExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when an assertion, an error, or a warning
+    /// directive is not followed by a message.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^error
+    /// ```
+    DiagnosticMessageExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when the condition of an assertion directive
+    /// evaluates to zero.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// |0100 @label
+    /// ^assert label<0100 "label is in the zero page"
+    /// ```
+    AssertionFailed {
+        /// The message of the assertion.
+        message: String,
+        /// Span of the assertion.
+        span: FileSpan,
+    },
+    /// This error gets reported by an error directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^error "this is not supported"
+    /// ```
+    Custom {
+        /// The message of the error.
+        message: String,
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when a repeat directive is not followed by a
+    /// hexadecimal number of repetitions.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^repeat { #00 }
+    /// ```
+    RepeatCountExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when the number of repetitions, and the optional
+    /// counter name, of a repeat directive are not followed by a block in braces.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^repeat 08 #00
+    /// ```
+    RepeatBodyExpected {
+        /// Span of the directive and the number of repetitions.
+        span: FileSpan,
+    },
+    /// This error gets reported when an `^otherwise` or an `^end` directive is
+    /// not inside a `^when` block, or when a `^do` or a `^loop` directive is not
+    /// inside a `^while` block.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^when ^loop
+    /// ```
+    NoMatchingBlock {
+        /// The directive that opens the expected block, i.e. `^when` or `^while`.
+        directive: String,
+        /// Span of the directive with no matching block.
+        span: FileSpan,
+    },
+    /// This error gets reported when a `^when` block is not closed with an
+    /// `^end` directive, or a `^while` block with a `^loop` directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^while #01 ^do
+    /// ```
+    NoMatchingBlockEnd {
+        /// The directive that opens the block, i.e. `^when` or `^while`.
+        directive: String,
+        /// Span of the directive that opens the block.
+        span: FileSpan,
+    },
+    /// This error gets reported when a `^when` block has more than one
+    /// `^otherwise` directive, or a `^while` block more than one `^do`
+    /// directive.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^when ^otherwise ^otherwise ^end
+    /// ```
+    BlockDirectiveDefinedMoreThanOnce {
+        /// The repeated directive, i.e. `^otherwise` or `^do`.
+        directive: String,
+        /// Span of the current directive.
+        span: FileSpan,
+        /// Span of the previous directive.
+        other_span: FileSpan,
+    },
+    /// This error gets reported when a module directive is not followed by the
+    /// name of the module.
+    ///
+    /// # Example
+    ///
+    /// ```uxntal
+    /// ^module
+    /// ```
+    ModuleNameExpected {
+        /// Span of the directive.
+        span: FileSpan,
+    },
+    /// This error gets reported when a label that is private to a module is
+    /// referenced from outside of it. Labels in modules are private unless their
+    /// names start with a capital letter.
+    ///
+    /// # Example
+    ///
+    /// `audio.tal`:
+    ///
+    /// ```uxntal
+    /// ^module audio
+    /// @reset BRK
+    /// ```
+    ///
+    /// `main.tal`:
+    ///
+    /// ```uxntal
+    /// ~audio.tal
+    /// ^module main
+    /// |0100 audio::reset
+    /// ```
+    LabelPrivate {
+        /// Name of the label, qualified with its module.
+        name: String,
+        /// Span of the label reference.
+        span: FileSpan,
+        /// Span of the label definition.
+        other_span: FileSpan,
     },
 }
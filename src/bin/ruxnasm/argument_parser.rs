@@ -9,6 +9,9 @@ const HELP_MESSAGE: &'static str = r#"Usage: ruxnasm [OPTIONS] INPUT OUTPUT
 Options:
     -h, --help          Display this message
     -V, --version       Print version info and exit
+    -I DIRECTORY        Add a directory to search for included files in
+    -D NAME[=VALUE]     Define a constant with a hexadecimal value (01 by default)
+    -s, --sym           Write a symbol file alongside the output file
 "#;
 const VERSION_MESSAGE: &'static str = concat!("ruxnasm ", env!("CARGO_PKG_VERSION"));
 
@@ -16,6 +19,9 @@ const VERSION_MESSAGE: &'static str = concat!("ruxnasm ", env!("CARGO_PKG_VERSIO
 pub struct Arguments {
     input_file_path: PathBuf,
     output_file_path: PathBuf,
+    include_paths: Vec<PathBuf>,
+    definitions: Vec<(String, u16)>,
+    symbols: bool,
 }
 
 impl Arguments {
@@ -26,6 +32,24 @@ impl Arguments {
     pub fn output_file_path(&self) -> &Path {
         &self.output_file_path
     }
+
+    pub fn include_paths(&self) -> &[PathBuf] {
+        &self.include_paths
+    }
+
+    pub fn definitions(&self) -> &[(String, u16)] {
+        &self.definitions
+    }
+
+    pub fn symbol_file_path(&self) -> Option<PathBuf> {
+        if self.symbols {
+            let mut symbol_file_path = self.output_file_path.clone().into_os_string();
+            symbol_file_path.push(".sym");
+            Some(symbol_file_path.into())
+        } else {
+            None
+        }
+    }
 }
 
 pub enum Error {
@@ -33,6 +57,8 @@ pub enum Error {
     NoOutputProvided,
     UnexpectedArgument { argument: String },
     UnrecognizedOption { option: String },
+    OptionValueExpected { option: String },
+    DefinitionInvalid { definition: String },
 }
 
 pub fn parse_arguments() -> Result<Arguments, Error> {
@@ -44,12 +70,16 @@ pub fn parse_arguments() -> Result<Arguments, Error> {
     args.next();
     let mut input_file_path: Option<PathBuf> = None;
     let mut output_file_path: Option<PathBuf> = None;
+    let mut include_paths: Vec<PathBuf> = Vec::new();
+    let mut definitions: Vec<(String, u16)> = Vec::new();
+    let mut symbols = false;
 
-    for arg in args {
+    while let Some(arg) = args.next() {
         if arg.starts_with("--") {
             match &arg[2..] {
                 "help" => exit_with_help_message(),
                 "version" => exit_with_version_message(),
+                "sym" => symbols = true,
                 option => {
                     return Err(Error::UnrecognizedOption {
                         option: option.to_owned(),
@@ -57,10 +87,24 @@ pub fn parse_arguments() -> Result<Arguments, Error> {
                 }
             }
         } else if arg.starts_with("-") {
-            for ch in arg[1..].chars() {
+            for (index, ch) in arg[1..].char_indices() {
                 match ch {
                     'h' => exit_with_help_message(),
                     'V' => exit_with_version_message(),
+                    's' => symbols = true,
+                    'I' => {
+                        include_paths
+                            .push(option_value(&arg[1 + index + 1..], ch, &mut args)?.into());
+                        break;
+                    }
+                    'D' => {
+                        definitions.push(parse_definition(option_value(
+                            &arg[1 + index + 1..],
+                            ch,
+                            &mut args,
+                        )?)?);
+                        break;
+                    }
                     option => {
                         return Err(Error::UnrecognizedOption {
                             option: option.to_string(),
@@ -85,12 +129,43 @@ pub fn parse_arguments() -> Result<Arguments, Error> {
         (Some(input_file_path), Some(output_file_path)) => Ok(Arguments {
             input_file_path,
             output_file_path,
+            include_paths,
+            definitions,
+            symbols,
         }),
         (None, _) => Err(Error::NoInputProvided),
         (_, None) => Err(Error::NoOutputProvided),
     }
 }
 
+/// Returns the value of a short option, which is either the rest of the argument containing the
+/// option, or the next argument.
+fn option_value(rest: &str, option: char, args: &mut env::Args) -> Result<String, Error> {
+    if !rest.is_empty() {
+        Ok(rest.to_owned())
+    } else {
+        args.next().ok_or(Error::OptionValueExpected {
+            option: option.to_string(),
+        })
+    }
+}
+
+fn parse_definition(definition: String) -> Result<(String, u16), Error> {
+    let (name, value) = match definition.find('=') {
+        Some(position) => (&definition[..position], Some(&definition[position + 1..])),
+        None => (definition.as_str(), None),
+    };
+    let value = match value {
+        Some(value) if value.len() <= 4 => u16::from_str_radix(value, 16).ok(),
+        Some(_) => None,
+        None => Some(0x01),
+    };
+    match value {
+        Some(value) if !name.is_empty() => Ok((name.to_owned(), value)),
+        _ => Err(Error::DefinitionInvalid { definition }),
+    }
+}
+
 fn exit_with_help_message() {
     println!("{}", HELP_MESSAGE);
     exit(0);
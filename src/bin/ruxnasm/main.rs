@@ -14,14 +14,23 @@ fn try_main() -> Result<(), ()> {
     match argument_parser::parse_arguments() {
         Ok(arguments) => match reader::read(arguments.input_file_path()) {
             Ok(input_file_contents) => {
-                let reporter = reporter::VoidReporter::new()
-                    .promote(arguments.input_file_path(), &input_file_contents);
-                match ruxnasm::assemble(&input_file_contents) {
-                    Ok((binary, warnings)) => {
+                let mut files = ruxnasm::Files::new();
+                let file_id = files.add(arguments.input_file_path(), input_file_contents);
+                let mut options = ruxnasm::Options::new();
+                for include_path in arguments.include_paths() {
+                    options = options.with_include_path(include_path);
+                }
+                for (name, value) in arguments.definitions() {
+                    options = options.with_definition(name, *value);
+                }
+                let result = ruxnasm::assemble_with(&mut files, file_id, &options);
+                let reporter = reporter::VoidReporter::new().promote(&files);
+                match result {
+                    Ok((binary, symbols, warnings)) => {
                         for warning in warnings {
                             reporter.emit(warning.into());
                         }
-                        match writer::write(arguments.output_file_path(), &binary) {
+                        match write_output(&arguments, &binary, &symbols) {
                             Ok(()) => Ok(()),
                             Err(error) => {
                                 let reporter = reporter.demote();
@@ -55,6 +64,18 @@ fn try_main() -> Result<(), ()> {
     }
 }
 
+fn write_output(
+    arguments: &argument_parser::Arguments,
+    binary: &[u8],
+    symbols: &[ruxnasm::Symbol],
+) -> Result<(), writer::Error> {
+    writer::write(arguments.output_file_path(), binary)?;
+    if let Some(symbol_file_path) = arguments.symbol_file_path() {
+        writer::write_symbols(&symbol_file_path, symbols)?;
+    }
+    Ok(())
+}
+
 fn main() {
     set_hook(Box::new(|panic_info| {
         let reporter = reporter::VoidReporter::new();
@@ -1,5 +1,3 @@
-use std::ops::Range;
-
 macro_rules! impl_severities {
     ($builder: ident) => {
         pub fn bug() -> $builder {
@@ -127,7 +125,7 @@ pub enum Severity {
 #[derive(Debug, Clone)]
 pub struct Label {
     pub style: LabelStyle,
-    pub span: Range<usize>,
+    pub span: ruxnasm::FileSpan,
     pub message: String,
 }
 
@@ -156,12 +154,12 @@ impl From<LabelStyle> for codespan_reporting::diagnostic::LabelStyle {
     }
 }
 
-impl From<Label> for codespan_reporting::diagnostic::Label<()> {
+impl From<Label> for codespan_reporting::diagnostic::Label<usize> {
     fn from(label: Label) -> Self {
         Self {
             style: label.style.into(),
-            file_id: (),
-            range: label.span,
+            file_id: label.span.file_id,
+            range: label.span.range,
             message: label.message,
         }
     }
@@ -198,7 +196,7 @@ impl From<VoidDiagnostic> for Vec<codespan_reporting::diagnostic::Diagnostic<()>
     }
 }
 
-impl From<FileDiagnostic> for Vec<codespan_reporting::diagnostic::Diagnostic<()>> {
+impl From<FileDiagnostic> for Vec<codespan_reporting::diagnostic::Diagnostic<usize>> {
     fn from(diagnostic: FileDiagnostic) -> Self {
         let mut labels = vec![diagnostic.label.into()];
         labels.extend(
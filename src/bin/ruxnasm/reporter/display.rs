@@ -28,6 +28,11 @@ impl From<argument_parser::Error> for VoidDiagnostic {
             argument_parser::Error::UnrecognizedOption { option } => {
                 VoidDiagnostic::error().with_message(format!("unrecognized option: '{}'", option))
             }
+            argument_parser::Error::OptionValueExpected { option } => VoidDiagnostic::error()
+                .with_message(format!("expected a value after option: '{}'", option)),
+            argument_parser::Error::DefinitionInvalid { definition } => VoidDiagnostic::error()
+                .with_message(format!("invalid definition: '{}'", definition))
+                .with_help("definitions have the form of NAME or NAME=value, where the value is a hexadecimal number"),
         }
     }
 }
@@ -108,13 +113,6 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     span,
                     message: String::new(),
                 }),
-            ruxnasm::Error::MoreThanOneSlashInIdentifier { span } => FileDiagnostic::error()
-                .with_message("identifiers can't have more than one '/' character")
-                .with_label(Label {
-                    style: LabelStyle::Primary,
-                    span,
-                    message: String::new(),
-                }),
             ruxnasm::Error::MoreThanOneByteFound { bytes, span } => FileDiagnostic::error()
                 .with_message("found more than one byte after a raw character rune")
                 .with_label(Label {
@@ -161,9 +159,43 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                 digit,
                 number,
                 span,
+            } => {
+                let diagnostic = FileDiagnostic::error()
+                    .with_message(format!(
+                        "invalid digit `{}` in a hexadecimal number `{}`",
+                        digit, number
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    });
+                match number_prefix_help(&number) {
+                    Some(help) => diagnostic.with_help(help),
+                    None => diagnostic,
+                }
+            }
+            ruxnasm::Error::DecimalDigitInvalid {
+                digit,
+                number,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "invalid digit `{}` in a decimal number `{}`",
+                    digit, number
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::BinaryDigitInvalid {
+                digit,
+                number,
+                span,
             } => FileDiagnostic::error()
                 .with_message(format!(
-                    "invalid digit `{}` in a hexadecimal number `{}`",
+                    "invalid digit `{}` in a binary number `{}`",
                     digit, number
                 ))
                 .with_label(Label {
@@ -171,6 +203,28 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     span,
                     message: String::new(),
                 }),
+            ruxnasm::Error::DigitExpected { span } => FileDiagnostic::error()
+                .with_message("expected at least one digit")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::NumberOutOfRange {
+                number,
+                minimum,
+                maximum,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "number `{}` is out of the range from {} to {}",
+                    number, minimum, maximum
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
             ruxnasm::Error::HexNumberUnevenLength {
                 length,
                 number,
@@ -223,12 +277,13 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     })
             }
             ruxnasm::Error::MacroUndefined { name, span } => FileDiagnostic::error()
-                .with_message(format!("macro `{}` is not defined", name))
+                .with_message(format!("neither a macro nor a label `{}` is defined", name))
                 .with_label(Label {
                     style: LabelStyle::Primary,
                     span,
                     message: String::new(),
-                }),
+                })
+                .with_note("macros have to be defined before they are expanded"),
             ruxnasm::Error::MacroDefinedMoreThanOnce {
                 name,
                 span,
@@ -261,13 +316,6 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     span: other_span,
                     message: format!("previous definition of label `{}` here", name),
                 }),
-            ruxnasm::Error::OpeningBraceNotAfterMacroDefinition { span } => FileDiagnostic::error()
-                .with_message("found an opening brace that is not a part of a macro definition")
-                .with_label(Label {
-                    style: LabelStyle::Primary,
-                    span,
-                    message: String::new(),
-                }),
             ruxnasm::Error::NoMatchingOpeningBrace { span } => FileDiagnostic::error()
                 .with_message("no matching opening brace found for a closing brace")
                 .with_label(Label {
@@ -292,6 +340,18 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     span,
                     message: String::new(),
                 }),
+            ruxnasm::Error::LocalLabelDefinedOutsideMacro { name, span } => {
+                FileDiagnostic::error()
+                    .with_message(format!(
+                        "local label `%{}` was defined outside of a macro",
+                        name
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    })
+            }
             ruxnasm::Error::NoMatchingOpeningBracket { span } => FileDiagnostic::error()
                 .with_message("no matching opening bracket found for a closing bracket")
                 .with_label(Label {
@@ -326,6 +386,18 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                         message: String::new(),
                     })
             }
+            ruxnasm::Error::LocalLabelReferencedOutsideMacro { name, span } => {
+                FileDiagnostic::error()
+                    .with_message(format!(
+                        "local label `%{}` was referenced outside of a macro",
+                        name
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    })
+            }
             ruxnasm::Error::LabelUndefined { name, span } => FileDiagnostic::error()
                 .with_message(format!("label `{}` is not defined", name))
                 .with_label(Label {
@@ -333,6 +405,26 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                     span,
                     message: String::new(),
                 }),
+            ruxnasm::Error::NoPrecedingNumericLabel { name, span } => FileDiagnostic::error()
+                .with_message(format!(
+                    "no numeric label `{}` is defined before this reference",
+                    name
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::NoFollowingNumericLabel { name, span } => FileDiagnostic::error()
+                .with_message(format!(
+                    "no numeric label `{}` is defined after this reference",
+                    name
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
             ruxnasm::Error::AddressNotZeroPage {
                 address,
                 identifier,
@@ -388,6 +480,16 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                         previous_pointer, desired_pointer
                     ),
                 }),
+            ruxnasm::Error::PadForwardReference { operand, span } => FileDiagnostic::error()
+                .with_message(format!(
+                    "pad `{}` refers to a label that is defined after it",
+                    operand
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "the address of the label would depend on this pad".to_owned(),
+                }),
             ruxnasm::Error::ProgramTooLong { span } => FileDiagnostic::error()
                 .with_message("program size exceeded 65536 bytes")
                 .with_label(Label {
@@ -438,22 +540,455 @@ impl From<ruxnasm::Error> for FileDiagnostic {
                         .with_note(format!("cannot invoke macro `{}`, because it would have infinite size if it were to be expanded", first_name))
                 }
             }
-        }
-    }
-}
-
-impl From<ruxnasm::Warning> for FileDiagnostic {
-    fn from(warning: ruxnasm::Warning) -> Self {
-        match warning {
-            ruxnasm::Warning::TokenTrimmed { span } => FileDiagnostic::warning()
+            ruxnasm::Error::FilePathExpected { span } => FileDiagnostic::error()
+                .with_message("expected a file path")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::FileNotFound { path, span } => FileDiagnostic::error()
+                .with_message(format!("couldn't find file `{}`", path))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_help("add the directory containing the file to the include paths with `-I`"),
+            ruxnasm::Error::CouldNotReadFile {
+                path,
+                message,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!("couldn't read file `{}`: {}", path, message))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::RecursiveInclude { path, span } => FileDiagnostic::error()
+                .with_message(format!("file `{}` includes itself", path))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(format!(
+                    "cannot include file `{}`, because it would have infinite size if it were to be included",
+                    path
+                )),
+            ruxnasm::Error::MacroParameterExpected { span } => FileDiagnostic::error()
+                .with_message("expected a parameter name after a colon")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::MacroParameterDefinedMoreThanOnce {
+                name,
+                span,
+                other_span,
+            } => FileDiagnostic::error()
+                .with_message(format!("parameter `{}` is defined multiple times", name))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("parameter `{}` redefined here", name),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("previous definition of parameter `{}` here", name),
+                }),
+            ruxnasm::Error::MacroArgumentExpected { span } => FileDiagnostic::error()
+                .with_message("expected an argument after a colon")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::MacroArgumentCountMismatch {
+                name,
+                expected,
+                found,
+                span,
+                other_span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "macro `{}` takes {} argument{}, but {} {} supplied",
+                    name,
+                    expected,
+                    if expected == 1 { "" } else { "s" },
+                    found,
+                    if found == 1 { "was" } else { "were" }
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("macro `{}` defined here", name),
+                }),
+            ruxnasm::Error::MacroParameterUndefined { name, span } => FileDiagnostic::error()
+                .with_message(format!("parameter `{}` is not defined", name))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::DirectiveExpected { span } => FileDiagnostic::error()
+                .with_message("expected a directive name")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::DirectiveUnknown { name, span } => FileDiagnostic::error()
+                .with_message(format!("unknown directive `{}`", name))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ConditionExpected { span } => FileDiagnostic::error()
+                .with_message("expected a condition after a conditional assembly directive")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(
+                    "`^ifdef` and `^ifndef` take a name, `^if` takes a name of a constant or a hexadecimal number",
+                ),
+            ruxnasm::Error::ConstantUndefined { name, span } => FileDiagnostic::error()
+                .with_message(format!("constant `{}` is not defined", name))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_help(format!("constants can be defined with the `-D {}=value` option", name)),
+            ruxnasm::Error::NoMatchingIf { span } => FileDiagnostic::error()
+                .with_message("no matching conditional assembly directive found")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::NoMatchingEndif { span } => FileDiagnostic::error()
+                .with_message("no matching `^endif` found for a conditional assembly directive")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ConstantNameExpected { span } => FileDiagnostic::error()
+                .with_message("expected a constant name")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ConstantValueExpected { span } => FileDiagnostic::error()
+                .with_message("expected a hexadecimal byte or short after the constant name")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::AlignmentExpected { span } => FileDiagnostic::error()
+                .with_message("expected a hexadecimal byte or short after the `^align` directive")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::AlignmentNotPowerOfTwo { alignment, span } => FileDiagnostic::error()
+                .with_message(format!("alignment {:#x} is not a power of two", alignment))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::FillLengthExpected { span } => FileDiagnostic::error()
+                .with_message("expected a hexadecimal byte or short after the `^fill` directive")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::FillValueExpected { span } => FileDiagnostic::error()
+                .with_message("expected a hexadecimal byte to fill with")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::BinarySliceOutOfBounds {
+                path,
+                offset,
+                length,
+                file_length,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(match length {
+                    Some(length) => format!(
+                        "slice of {:#x} bytes at offset {:#x} is out of bounds of `{}`",
+                        length, offset, path
+                    ),
+                    None => format!("offset {:#x} is out of bounds of `{}`", offset, path),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(format!("the file is {:#x} bytes long", file_length)),
+            ruxnasm::Error::EscapeSequenceInvalid { sequence, span } => FileDiagnostic::error()
+                .with_message(format!("invalid escape sequence `{}`", sequence))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note(
+                    "valid escape sequences are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"` and `\\xNN`",
+                ),
+            ruxnasm::Error::StringTooLong { length, span } => FileDiagnostic::error()
+                .with_message(format!(
+                    "string of {} bytes is too long to be prefixed with its length",
+                    length
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note("length-prefixed strings can be at most 255 bytes long"),
+            ruxnasm::Error::SectionNameExpected { span } => FileDiagnostic::error()
+                .with_message("expected a section name")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::AbsolutePadInFloatingSection { section, span } => {
+                FileDiagnostic::error()
+                    .with_message(format!(
+                        "absolute pad in the floating section `{}`",
+                        section
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    })
+                    .with_note(
+                        "floating sections are placed after the `code` section once the whole program is assembled",
+                    )
+            }
+            ruxnasm::Error::AllocationNameExpected { span } => FileDiagnostic::error()
+                .with_message("expected a label name to allocate zero-page bytes to")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::AllocationSizeExpected { span } => FileDiagnostic::error()
+                .with_message("expected a hexadecimal number of bytes to allocate")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ZeroPageExhausted { name, size, span } => FileDiagnostic::error()
+                .with_message(format!(
+                    "not enough bytes left in the zero page to allocate {} bytes to `{}`",
+                    size, name
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_note("the zero page has 256 bytes"),
+            ruxnasm::Error::AssertionConditionExpected { span } => FileDiagnostic::error()
+                .with_message("expected a condition to assert")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::DiagnosticMessageExpected { span } => FileDiagnostic::error()
+                .with_message("expected a message")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::AssertionFailed { message, span } => FileDiagnostic::error()
+                .with_message(format!("assertion failed: {}", message))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::Custom { message, span } => FileDiagnostic::error()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::RepeatCountExpected { span } => FileDiagnostic::error()
+                .with_message("expected a number of repetitions")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::RepeatBodyExpected { span } => FileDiagnostic::error()
+                .with_message("expected a block to repeat")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_help("enclose the repeated words in braces"),
+            ruxnasm::Error::NoMatchingBlock { directive, span } => FileDiagnostic::error()
+                .with_message(format!("no matching `{}` block found", directive))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::NoMatchingBlockEnd { directive, span } => FileDiagnostic::error()
                 .with_message(format!(
-                    "token has been cut off, as it's longer than 64 characters"
+                    "no matching `{}` found for a `{}` block",
+                    if directive == "^when" { "^end" } else { "^loop" },
+                    directive
                 ))
                 .with_label(Label {
                     style: LabelStyle::Primary,
                     span,
                     message: String::new(),
                 }),
+            ruxnasm::Error::BlockDirectiveDefinedMoreThanOnce {
+                directive,
+                span,
+                other_span,
+            } => FileDiagnostic::error()
+                .with_message(format!("`{}` is defined multiple times in a block", directive))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: format!("`{}` redefined here", directive),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: format!("previous `{}` here", directive),
+                }),
+            ruxnasm::Error::ModuleNameExpected { span } => FileDiagnostic::error()
+                .with_message("expected a module name")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::LabelPrivate {
+                name,
+                span,
+                other_span,
+            } => FileDiagnostic::error()
+                .with_message(format!("label `{}` is private to its module", name))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: "label defined here".to_owned(),
+                })
+                .with_help("capitalize the name of the label to export it from the module"),
+            ruxnasm::Error::BytesInUninitializedSection { section, span } => {
+                FileDiagnostic::error()
+                    .with_message(format!(
+                        "section `{}` can only reserve memory with relative pads",
+                        section
+                    ))
+                    .with_label(Label {
+                        style: LabelStyle::Primary,
+                        span,
+                        message: String::new(),
+                    })
+            }
+            ruxnasm::Error::ExpressionOverflow {
+                value,
+                maximum,
+                span,
+            } => FileDiagnostic::error()
+                .with_message(format!(
+                    "value {} of the expression does not fit in the range from 0 to {:#x}",
+                    value, maximum
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::DivisionByZero { span } => FileDiagnostic::error()
+                .with_message("division by zero")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "this evaluates to zero".to_owned(),
+                }),
+            ruxnasm::Error::OperandExpected { span } => FileDiagnostic::error()
+                .with_message("expected a label, a constant or a hexadecimal number")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::OperatorExpected { span } => FileDiagnostic::error()
+                .with_message("expected an operator")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
+            ruxnasm::Error::ExpressionNotClosed { span } => FileDiagnostic::error()
+                .with_message("expression is not closed")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "expected a closing backtick at the end".to_owned(),
+                }),
+            ruxnasm::Error::ElseDefinedMoreThanOnce { span, other_span } => FileDiagnostic::error()
+                .with_message("`^else` is defined multiple times in a conditional assembly block")
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: "`^else` redefined here".to_owned(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: "previous `^else` here".to_owned(),
+                }),
+        }
+    }
+}
+
+impl From<ruxnasm::Warning> for FileDiagnostic {
+    fn from(warning: ruxnasm::Warning) -> Self {
+        match warning {
             ruxnasm::Warning::InstructionModeDefinedMoreThanOnce {
                 instruction_mode,
                 instruction,
@@ -489,6 +1024,60 @@ impl From<ruxnasm::Warning> for FileDiagnostic {
                     message: String::new(),
                 })
                 .with_help("if this is intentional, prefix it with a capital letter"),
+            ruxnasm::Warning::LabelCollidesWhenTrimmed {
+                name,
+                other_name,
+                span,
+                other_span,
+            } => FileDiagnostic::warning()
+                .with_message(format!(
+                    "label `{}` collides with label `{}` when cut off after 64 characters",
+                    name, other_name
+                ))
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                })
+                .with_label(Label {
+                    style: LabelStyle::Secondary,
+                    span: other_span,
+                    message: "colliding label defined here".to_owned(),
+                })
+                .with_note("uxnasm cuts label names off after 64 characters"),
+            ruxnasm::Warning::Custom { message, span } => FileDiagnostic::warning()
+                .with_message(message)
+                .with_label(Label {
+                    style: LabelStyle::Primary,
+                    span,
+                    message: String::new(),
+                }),
         }
     }
 }
+
+/// Returns a hint for a hexadecimal number that is likely meant to be a binary or a decimal number
+/// with a `b` or a `d` prefix, like `b1010_0101` or `d2_000`.
+fn number_prefix_help(number: &str) -> Option<String> {
+    let (prefix, digits, replacement) = if let Some(digits) = number.strip_prefix('b') {
+        ("b", digits, "%")
+    } else if let Some(digits) = number.strip_prefix('d') {
+        ("d", digits, "+")
+    } else {
+        return None;
+    };
+    let is_digit = |ch: char| match prefix {
+        "b" => ch == '0' || ch == '1' || ch == '_',
+        _ => ch.is_ascii_digit() || ch == '_',
+    };
+    if digits.is_empty() || !digits.chars().all(is_digit) {
+        return None;
+    }
+    Some(format!(
+        "numbers with a `{}` prefix are hexadecimal, write `{}{}` for a {} number",
+        prefix,
+        replacement,
+        digits,
+        if prefix == "b" { "binary" } else { "decimal" }
+    ))
+}
@@ -44,28 +44,52 @@ impl<'a> File<'a> {
     }
 }
 
-impl<'a> files::Files<'a> for File<'a> {
-    type FileId = ();
+/// All source files that took part in the assembly.
+#[derive(Debug, Clone)]
+pub struct Files<'a> {
+    files: Vec<File<'a>>,
+}
+
+impl<'a> Files<'a> {
+    pub fn new(files: &'a ruxnasm::Files) -> Self {
+        Self {
+            files: files
+                .ids()
+                .map(|file_id| File::new(files.path(file_id), files.source(file_id)))
+                .collect(),
+        }
+    }
+
+    fn get(&self, file_id: usize) -> Result<&File<'a>, files::Error> {
+        self.files.get(file_id).ok_or(files::Error::FileMissing)
+    }
+}
+
+impl<'a> files::Files<'a> for Files<'a> {
+    type FileId = usize;
     type Name = &'a str;
     type Source = &'a str;
 
-    fn name(&self, _file_id: ()) -> Result<&str, files::Error> {
-        Ok(&self.name)
+    fn name(&'a self, file_id: usize) -> Result<&'a str, files::Error> {
+        Ok(&self.get(file_id)?.name)
     }
 
-    fn source(&self, _file_id: ()) -> Result<&str, files::Error> {
-        Ok(&self.source)
+    fn source(&'a self, file_id: usize) -> Result<&'a str, files::Error> {
+        Ok(&self.get(file_id)?.source)
     }
 
-    fn line_index(&self, _file_id: (), byte_index: usize) -> Result<usize, files::Error> {
-        self.line_starts
+    fn line_index(&self, file_id: usize, byte_index: usize) -> Result<usize, files::Error> {
+        Ok(self
+            .get(file_id)?
+            .line_starts
             .binary_search(&byte_index)
-            .or_else(|next_line| Ok(next_line - 1))
+            .unwrap_or_else(|next_line| next_line - 1))
     }
 
-    fn line_range(&self, _file_id: (), line_index: usize) -> Result<Range<usize>, files::Error> {
-        let line_start = self.line_start(line_index)?;
-        let next_line_start = self.line_start(line_index + 1)?;
+    fn line_range(&self, file_id: usize, line_index: usize) -> Result<Range<usize>, files::Error> {
+        let file = self.get(file_id)?;
+        let line_start = file.line_start(line_index)?;
+        let next_line_start = file.line_start(line_index + 1)?;
 
         Ok(line_start..next_line_start)
     }
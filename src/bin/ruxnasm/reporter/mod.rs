@@ -1,6 +1,6 @@
 use diagnostic::{FileDiagnostic, VoidDiagnostic};
-use file::{File, Void};
-use std::{path::Path, sync::RwLock};
+use file::{Files, Void};
+use std::sync::RwLock;
 
 mod diagnostic;
 mod display;
@@ -31,9 +31,9 @@ impl VoidReporter {
         }
     }
 
-    pub fn promote<'a>(self, file_path: &'a Path, file_contents: &'a [u8]) -> FileReporter<'a> {
+    pub fn promote(self, files: &ruxnasm::Files) -> FileReporter<'_> {
         FileReporter {
-            file: File::new(file_path, file_contents),
+            files: Files::new(files),
             writer: self.writer,
             config: self.config,
         }
@@ -54,7 +54,7 @@ impl VoidReporter {
 }
 
 pub struct FileReporter<'a> {
-    pub file: File<'a>,
+    pub files: Files<'a>,
     pub writer: RwLock<codespan_reporting::term::termcolor::StandardStream>,
     pub config: codespan_reporting::term::Config,
 }
@@ -68,13 +68,13 @@ impl<'a> FileReporter<'a> {
     }
 
     pub fn emit(&self, diagnostic: FileDiagnostic) {
-        let codespan_diagnostics: Vec<codespan_reporting::diagnostic::Diagnostic<()>> =
+        let codespan_diagnostics: Vec<codespan_reporting::diagnostic::Diagnostic<usize>> =
             diagnostic.into();
         for codespan_diagnostic in codespan_diagnostics {
             let _ = codespan_reporting::term::emit(
                 &mut self.writer.write().unwrap().lock(),
                 &self.config,
-                &self.file,
+                &self.files,
                 &codespan_diagnostic,
             );
         }
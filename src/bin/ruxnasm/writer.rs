@@ -23,3 +23,15 @@ pub fn write(path: &Path, binary: &[u8]) -> Result<(), Error> {
         })?;
     Ok(())
 }
+
+/// Writes a symbol file in the format used by Uxnasm: for every symbol, a big-endian 16-bit
+/// address, followed by the null-terminated name of the symbol.
+pub fn write_symbols(path: &Path, symbols: &[ruxnasm::Symbol]) -> Result<(), Error> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for symbol in symbols {
+        bytes.extend_from_slice(&symbol.address.to_be_bytes());
+        bytes.extend_from_slice(symbol.name.as_bytes());
+        bytes.push(0);
+    }
+    write(path, &bytes)
+}
@@ -0,0 +1,114 @@
+use crate::{token::ScopedIdentifier, FileId};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the label that the built-in symbols are sublabels of, e.g. `ruxnasm/version`.
+const LABEL: &[u8] = b"ruxnasm";
+
+/// A symbol defined by the assembler for every program.
+///
+/// In a raw word, like `"ruxnasm/version`, a built-in symbol is replaced with its text. Anywhere a
+/// label can be referenced, like in ``#`ruxnasm/length` `` or `;ruxnasm/date`, it stands for its
+/// numeric value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    /// The name of the file that the symbol appears in. It only has a text.
+    File,
+    /// The version of the assembler, e.g. `0.2.0`. Its numeric value is the major version in the
+    /// high byte and the minor version in the low byte.
+    Version,
+    /// The date of the build, e.g. `2021-05-31`. Its numeric value is the number of days since
+    /// `1970-01-01`.
+    Date,
+    /// The length of the program in bytes, i.e. the size of the binary. Its text is the length
+    /// as a four-digit hexadecimal number.
+    Length,
+}
+
+impl Builtin {
+    /// Returns the built-in symbol with the given name, if there is one.
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        let sublabel = name.strip_prefix(LABEL)?.strip_prefix(b"/")?;
+        match sublabel {
+            b"file" => Some(Self::File),
+            b"version" => Some(Self::Version),
+            b"date" => Some(Self::Date),
+            b"length" => Some(Self::Length),
+            _ => None,
+        }
+    }
+
+    pub fn identifier(&self) -> ScopedIdentifier {
+        let sublabel: &[u8] = match self {
+            Self::File => b"file",
+            Self::Version => b"version",
+            Self::Date => b"date",
+            Self::Length => b"length",
+        };
+        ScopedIdentifier::Sublabel(LABEL.to_vec(), vec![sublabel.to_vec()])
+    }
+
+    pub fn is_builtin(scoped_identifier: &ScopedIdentifier) -> bool {
+        matches!(scoped_identifier, ScopedIdentifier::Sublabel(label, _) if label == LABEL)
+            && Self::from_name(scoped_identifier.to_string().as_bytes()).is_some()
+    }
+}
+
+/// What the values of the built-in symbols are derived from.
+pub(crate) struct Environment {
+    /// Names of the files taking part in the assembly, indexed by their identifiers.
+    pub file_names: Vec<String>,
+    /// The file containing the program.
+    pub main_file: FileId,
+    /// Time of the build in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// Returns the version of the assembler, with the major version in the high byte and the minor
+/// version in the low byte.
+pub(crate) fn version_number() -> u16 {
+    let major: u16 = env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or(0);
+    let minor: u16 = env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or(0);
+    (major.min(0xff) << 8) | minor.min(0xff)
+}
+
+/// Returns the version of the assembler as text.
+pub(crate) fn version_text() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Returns the time of the build in seconds since the Unix epoch, taken from the
+/// `SOURCE_DATE_EPOCH` environment variable for reproducible builds, or from the system clock if
+/// the variable is not set.
+pub(crate) fn timestamp() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0)
+        })
+}
+
+/// Returns the date, in the `YYYY-MM-DD` format, that is the given number of days after
+/// `1970-01-01`.
+pub(crate) fn date_text(days: u64) -> String {
+    // Converts the days to a date in the proleptic Gregorian calendar, with years that start in
+    // March, so that the leap day is the last day of a year.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -1,14 +1,18 @@
 use std::collections::HashSet;
 
 use crate::{
+    expression,
     span::{Span, Spanned},
-    token::{ScopedIdentifier, Statement},
+    token::{Direction, Reference, ScopedIdentifier, Statement},
     walker::Definitions,
     Error, Warning,
 };
 
 const LIT: u8 = 0x80;
 const LIT2: u8 = 0x20;
+const JMP2: u8 = 0x2c;
+const JCN2: u8 = 0x2d;
+const JSR2: u8 = 0x2e;
 
 struct Binary {
     data: [u8; 256 * 256 - 256],
@@ -28,7 +32,7 @@ impl Binary {
     pub fn push_byte(&mut self, byte: u8) {
         self.data[self.pointer as usize - 256] = byte;
         self.increment_pointer(1);
-        self.length = self.pointer;
+        self.length = self.length.max(self.pointer);
     }
 
     pub fn push_short(&mut self, short: u16) {
@@ -36,6 +40,13 @@ impl Binary {
         self.push_byte((short & 0x00ff) as u8);
     }
 
+    /// Pushes the `address` as a short literal, followed by the jump `opcode` that consumes it.
+    pub fn push_jump(&mut self, opcode: u8, address: u16) {
+        self.push_byte(LIT2);
+        self.push_short(address);
+        self.push_byte(opcode);
+    }
+
     pub fn set_pointer(&mut self, to: u16) {
         self.pointer = to;
     }
@@ -62,11 +73,15 @@ pub(crate) fn emit(
     let mut errors: Vec<Error> = Vec::new();
     let mut warnings: Vec<Warning> = Vec::new();
 
-    let mut unused_labels: HashSet<&ScopedIdentifier> = definitions.labels.keys().collect();
+    let mut unused_labels: HashSet<&ScopedIdentifier> = definitions
+        .labels
+        .keys()
+        .filter(|scoped_identifier| !definitions.used_labels.contains(scoped_identifier))
+        .collect();
 
     let mut binary = Binary::new();
 
-    for statement in statements {
+    for (index, statement) in statements.into_iter().enumerate() {
         match statement {
             Spanned {
                 node: Statement::Instruction(instruction),
@@ -91,18 +106,25 @@ pub(crate) fn emit(
                 binary.increment_pointer(value as u16);
             }
             Spanned {
-                node: Statement::LiteralZeroPageAddress(scoped_identifier),
+                node: Statement::Fill(length, value),
+                ..
+            } => {
+                for _ in 0..length {
+                    binary.push_byte(value);
+                }
+            }
+            Spanned {
+                node: Statement::LiteralZeroPageAddress(reference),
                 span,
-            } => match find_address(&scoped_identifier, &definitions, &span) {
-                Ok((address, _)) => {
-                    unused_labels.remove(&scoped_identifier);
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, _, name)) => {
                     if address <= 0xff {
                         binary.push_byte(LIT);
                         binary.push_byte((address & 0xff) as u8);
                     } else {
                         errors.push(Error::AddressNotZeroPage {
                             address,
-                            identifier: scoped_identifier.to_string(),
+                            identifier: name,
                             span: span.into(),
                         });
                         binary.increment_pointer(2);
@@ -114,16 +136,15 @@ pub(crate) fn emit(
                 }
             },
             Spanned {
-                node: Statement::LiteralRelativeAddress(scoped_identifier),
+                node: Statement::LiteralRelativeAddress(reference),
                 span,
-            } => match find_address(&scoped_identifier, &definitions, &span) {
-                Ok((address, other_span)) => {
-                    unused_labels.remove(&scoped_identifier);
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, other_span, name)) => {
                     let offset = address as isize - binary.get_pointer() as isize - 3;
                     if offset < -126 || offset > 126 {
                         errors.push(Error::AddressTooFar {
                             distance: offset.abs() as usize,
-                            identifier: scoped_identifier.to_string(),
+                            identifier: name,
                             span: span.into(),
                             other_span: other_span.into(),
                         });
@@ -139,11 +160,10 @@ pub(crate) fn emit(
                 }
             },
             Spanned {
-                node: Statement::LiteralAbsoluteAddress(scoped_identifier),
+                node: Statement::LiteralAbsoluteAddress(reference),
                 span,
-            } => match find_address(&scoped_identifier, &definitions, &span) {
-                Ok((address, _)) => {
-                    unused_labels.remove(&scoped_identifier);
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, _, _)) => {
                     binary.push_byte(LIT2);
                     binary.push_short(address);
                 }
@@ -153,11 +173,54 @@ pub(crate) fn emit(
                 }
             },
             Spanned {
-                node: Statement::RawAddress(scoped_identifier),
+                node: Statement::RawZeroPageAddress(reference),
+                span,
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, _, name)) => {
+                    if address <= 0xff {
+                        binary.push_byte((address & 0xff) as u8);
+                    } else {
+                        errors.push(Error::AddressNotZeroPage {
+                            address,
+                            identifier: name,
+                            span: span.into(),
+                        });
+                        binary.increment_pointer(1);
+                    }
+                }
+                Err(err) => {
+                    errors.push(err);
+                    binary.increment_pointer(1);
+                }
+            },
+            Spanned {
+                node: Statement::RawRelativeAddress(reference),
+                span,
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, other_span, name)) => {
+                    let offset = address as isize - binary.get_pointer() as isize - 2;
+                    if offset < -126 || offset > 126 {
+                        errors.push(Error::AddressTooFar {
+                            distance: offset.abs() as usize,
+                            identifier: name,
+                            span: span.into(),
+                            other_span: other_span.into(),
+                        });
+                        binary.increment_pointer(1);
+                    } else {
+                        binary.push_byte(offset as u8);
+                    }
+                }
+                Err(err) => {
+                    errors.push(err);
+                    binary.increment_pointer(1);
+                }
+            },
+            Spanned {
+                node: Statement::RawAddress(reference),
                 span,
-            } => match find_address(&scoped_identifier, &definitions, &span) {
-                Ok((address, _)) => {
-                    unused_labels.remove(&scoped_identifier);
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, _, _)) => {
                     binary.push_short(address);
                 }
                 Err(err) => {
@@ -165,6 +228,55 @@ pub(crate) fn emit(
                     binary.increment_pointer(2);
                 }
             },
+            Spanned {
+                node: Statement::ImmediateJump(reference),
+                span,
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, _, _)) => {
+                    binary.push_jump(JMP2, address);
+                }
+                Err(err) => {
+                    errors.push(err);
+                    binary.increment_pointer(4);
+                }
+            },
+            Spanned {
+                node: Statement::ImmediateConditionalJump(reference),
+                span,
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, _, _)) => {
+                    binary.push_jump(JCN2, address);
+                }
+                Err(err) => {
+                    errors.push(err);
+                    binary.increment_pointer(4);
+                }
+            },
+            Spanned {
+                node: Statement::ImmediateCall(reference),
+                span,
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((address, _, _)) => {
+                    binary.push_jump(JSR2, address);
+                }
+                Err(err) => {
+                    // A bare word that names nothing at all is more likely a misspelled macro
+                    // than a misspelled label.
+                    match (&err, &reference.identifier) {
+                        (
+                            Error::LabelUndefined { .. },
+                            Some(identifier @ ScopedIdentifier::Label(_)),
+                        ) if identifier.module().is_none() => {
+                            errors.push(Error::MacroUndefined {
+                                name: name_of(&reference),
+                                span: span.into(),
+                            });
+                        }
+                        _ => errors.push(err),
+                    }
+                    binary.increment_pointer(4);
+                }
+            },
             Spanned {
                 node: Statement::LiteralHexByte(value),
                 ..
@@ -179,6 +291,40 @@ pub(crate) fn emit(
                 binary.push_byte(LIT2);
                 binary.push_short(value);
             }
+            Spanned {
+                node: Statement::LiteralByteExpression(reference),
+                span,
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((value, _, _)) if value <= 0xff => {
+                    binary.push_byte(LIT);
+                    binary.push_byte(value as u8);
+                }
+                Ok((value, _, _)) => {
+                    errors.push(Error::ExpressionOverflow {
+                        value: value as i64,
+                        maximum: 0xff,
+                        span: span.into(),
+                    });
+                    binary.increment_pointer(2);
+                }
+                Err(err) => {
+                    errors.push(err);
+                    binary.increment_pointer(2);
+                }
+            },
+            Spanned {
+                node: Statement::LiteralShortExpression(reference),
+                span,
+            } => match resolve(&reference, index, &definitions, &mut unused_labels, &span) {
+                Ok((value, _, _)) => {
+                    binary.push_byte(LIT2);
+                    binary.push_short(value);
+                }
+                Err(err) => {
+                    errors.push(err);
+                    binary.increment_pointer(3);
+                }
+            },
             Spanned {
                 node: Statement::RawHexByte(value),
                 ..
@@ -205,12 +351,44 @@ pub(crate) fn emit(
                     binary.push_byte(byte);
                 }
             }
+            Spanned {
+                node: Statement::Assertion(reference, message),
+                span,
+            } => match expression::evaluate(
+                &reference.expression,
+                &reference.scope,
+                reference.expansion,
+                reference.namespace.as_deref(),
+                &definitions.labels,
+            ) {
+                Ok(evaluation) => {
+                    for identifier in &evaluation.identifiers {
+                        unused_labels.remove(identifier);
+                    }
+                    if evaluation.value == 0 {
+                        errors.push(Error::AssertionFailed {
+                            message,
+                            span: span.into(),
+                        });
+                    }
+                }
+                Err(err) => errors.push(err),
+            },
+            Spanned {
+                node: Statement::IncludedBinary(bytes),
+                ..
+            } => {
+                for byte in bytes {
+                    binary.push_byte(byte);
+                }
+            }
         }
     }
 
     for unused_label_name in unused_labels
         .into_iter()
         .filter(|scoped_identifier| !scoped_identifier.is_captital())
+        .filter(|scoped_identifier| !definitions.constants.contains(scoped_identifier))
     {
         let (_, span) = definitions.labels[&unused_label_name];
         warnings.push(Warning::LabelUnused {
@@ -226,20 +404,197 @@ pub(crate) fn emit(
     }
 }
 
-fn find_address(
-    scoped_identifier: &ScopedIdentifier,
+/// Resolves a reference in the statement at the `index` to the address of a label, or to the value
+/// of an expression, marking the labels that it uses as used. Returns the value, the span of the
+/// definition of the first used label, and the name of the reference as written.
+fn resolve(
+    reference: &Reference,
+    index: usize,
     definitions: &Definitions,
+    unused_labels: &mut HashSet<&ScopedIdentifier>,
     span: &Span,
-) -> Result<(u16, Span), Error> {
-    match definitions.labels.get(scoped_identifier) {
-        Some((address, span)) => {
-            return Ok((*address, *span));
+) -> Result<(u16, Span, String), Error> {
+    if let Some(ScopedIdentifier::Numeric(name, direction)) = &reference.identifier {
+        return resolve_numeric(name, *direction, index, definitions, span);
+    }
+    if let Some(identifier) = &reference.identifier {
+        let namespace = reference.namespace.as_deref();
+        if let Some((identifier, (address, other_span))) =
+            expression::lookup(&definitions.labels, identifier, namespace)
+        {
+            if identifier.is_private_to_other(namespace) {
+                return Err(Error::LabelPrivate {
+                    name: identifier.to_string(),
+                    span: (*span).into(),
+                    other_span: (*other_span).into(),
+                });
+            }
+            unused_labels.remove(identifier);
+            return Ok((*address, *other_span, identifier.to_string()));
         }
+        return Err(Error::LabelUndefined {
+            name: identifier.to_string(),
+            span: (*span).into(),
+        });
+    }
+
+    let evaluation = expression::evaluate(
+        &reference.expression,
+        &reference.scope,
+        reference.expansion,
+        reference.namespace.as_deref(),
+        &definitions.labels,
+    )?;
+    for identifier in &evaluation.identifiers {
+        unused_labels.remove(identifier);
+    }
+    if !(0..=0xffff).contains(&evaluation.value) {
+        return Err(Error::ExpressionOverflow {
+            value: evaluation.value,
+            maximum: 0xffff,
+            span: (*span).into(),
+        });
+    }
+    Ok((
+        evaluation.value as u16,
+        evaluation.other_span.unwrap_or(*span),
+        name_of(reference),
+    ))
+}
+
+/// Resolves a reference in the statement at the `index` to the address of the nearest definition
+/// of a numeric label in the given direction.
+fn resolve_numeric(
+    name: &[u8],
+    direction: Direction,
+    index: usize,
+    definitions: &Definitions,
+    span: &Span,
+) -> Result<(u16, Span, String), Error> {
+    let candidates = match definitions.numeric_labels.get(name) {
+        Some(candidates) => candidates.as_slice(),
+        None => &[],
+    };
+    let definition = match direction {
+        Direction::Backward => candidates
+            .iter()
+            .rev()
+            .find(|(position, _, _)| *position <= index),
+        Direction::Forward => candidates.iter().find(|(position, _, _)| *position > index),
+    };
+    match definition {
+        Some((_, address, other_span)) => Ok((
+            *address,
+            *other_span,
+            ScopedIdentifier::Numeric(name.to_vec(), direction).to_string(),
+        )),
         None => {
-            return Err(Error::LabelUndefined {
-                name: scoped_identifier.to_string(),
-                span: (*span).into(),
-            });
+            let name = String::from_utf8_lossy(name).into_owned();
+            let span = (*span).into();
+            Err(match direction {
+                Direction::Backward => Error::NoPrecedingNumericLabel { name, span },
+                Direction::Forward => Error::NoFollowingNumericLabel { name, span },
+            })
         }
     }
 }
+
+/// Returns the name of a reference as written in the source.
+fn name_of(reference: &Reference) -> String {
+    match &reference.identifier {
+        Some(identifier) => identifier.to_string(),
+        None => reference
+            .expression
+            .iter()
+            .map(|symbol| symbol.node as char)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assemble, Error};
+
+    fn errors(source: &str) -> Vec<Error> {
+        match assemble(source.as_bytes()) {
+            Ok((binary, _)) => panic!("expected errors, got {:02x?}", binary),
+            Err((errors, _)) => errors,
+        }
+    }
+
+    #[test]
+    fn raw_address_out_of_range() {
+        assert!(matches!(
+            errors("|0100 @Main -Main")[..],
+            [Error::AddressNotZeroPage { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 @Main _far |0200 @far")[..],
+            [Error::AddressTooFar { .. }]
+        ));
+    }
+
+    #[test]
+    fn numeric_label_not_found() {
+        assert!(matches!(
+            errors("|0100 @Main ,1b JMP &1")[..],
+            [Error::NoPrecedingNumericLabel { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 @Main &1 ,1f JMP")[..],
+            [Error::NoFollowingNumericLabel { .. }]
+        ));
+    }
+
+    #[test]
+    fn expression_errors() {
+        assert!(matches!(
+            errors("|0100 ;`label/0` @label")[..],
+            [Error::DivisionByZero { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ;`ffff+2`")[..],
+            [Error::ExpressionOverflow { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ;`label+` @label")[..],
+            [Error::OperandExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 #`1=2`")[..],
+            [Error::OperatorExpected { .. }]
+        ));
+    }
+
+    #[test]
+    fn assertion_failed() {
+        assert!(matches!(
+            errors("|0100 @label ^assert label<0100 \"label is in the zero page\"")[..],
+            [Error::AssertionFailed { .. }]
+        ));
+    }
+
+    #[test]
+    fn immediate_call_undefined() {
+        assert!(matches!(
+            errors("|0100 nope")[..],
+            [Error::MacroUndefined { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 @Main Main/nope")[..],
+            [Error::LabelUndefined { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 audio::nope")[..],
+            [Error::LabelUndefined { .. }]
+        ));
+    }
+
+    #[test]
+    fn label_private() {
+        assert!(matches!(
+            errors("|0100 audio::reset BRK ~tests/suite/module/basic/audio.tal")[..],
+            [Error::LabelPrivate { .. }]
+        ));
+    }
+}
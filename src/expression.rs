@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use crate::{token::ScopedIdentifier, Error, Span, Spanned};
+
+/// Characters that can appear in expressions as operators.
+const OPERATORS: [u8; 10] = [b'+', b'-', b'*', b'/', b'&', b'|', b'<', b'>', b'=', b'!'];
+
+fn is_operator(ch: u8) -> bool {
+    OPERATORS.contains(&ch)
+}
+
+/// The result of evaluating an expression.
+pub(crate) struct Evaluation {
+    pub value: i64,
+    /// Span of the definition of the first label that appears in the expression.
+    pub other_span: Option<Span>,
+    /// Labels that appear in the expression.
+    pub identifiers: Vec<ScopedIdentifier>,
+}
+
+/// Evaluates an expression over labels, constants and hexadecimal numbers.
+///
+/// Binary operators, from the highest to the lowest precedence, are `*` and `/`, `+` and `-`,
+/// `<<` and `>>`, the comparisons `<`, `<=`, `>` and `>=`, the comparisons `==` and `!=`, `&`,
+/// and `|`. All of them are left-associative, and the comparisons evaluate to either 1 or 0. A
+/// primary operand can be prefixed with `<` or `>` to select its low or high byte, respectively.
+pub(crate) fn evaluate(
+    symbols: &[Spanned<u8>],
+    scope: &[Vec<u8>],
+    expansion: Option<usize>,
+    namespace: Option<&[u8]>,
+    labels: &HashMap<ScopedIdentifier, (u16, Span)>,
+) -> Result<Evaluation, Error> {
+    let mut evaluator = Evaluator {
+        symbols,
+        position: 0,
+        scope,
+        expansion,
+        namespace,
+        labels,
+        other_span: None,
+        identifiers: Vec::new(),
+    };
+    let (value, _) = evaluator.evaluate_binary(0)?;
+    if let Some(symbol) = symbols.get(evaluator.position) {
+        return Err(Error::OperatorExpected {
+            span: symbol.span.into(),
+        });
+    }
+    Ok(Evaluation {
+        value,
+        other_span: evaluator.other_span,
+        identifiers: evaluator.identifiers,
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Operator {
+    Multiply,
+    Divide,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+}
+
+impl Operator {
+    fn precedence(&self) -> usize {
+        match self {
+            Self::Or => 0,
+            Self::And => 1,
+            Self::Equal | Self::NotEqual => 2,
+            Self::Less | Self::LessOrEqual | Self::Greater | Self::GreaterOrEqual => 3,
+            Self::ShiftLeft | Self::ShiftRight => 4,
+            Self::Add | Self::Subtract => 5,
+            Self::Multiply | Self::Divide => 6,
+        }
+    }
+}
+
+const PRECEDENCE_LEVELS: usize = 7;
+
+struct Evaluator<'a> {
+    symbols: &'a [Spanned<u8>],
+    position: usize,
+    scope: &'a [Vec<u8>],
+    expansion: Option<usize>,
+    namespace: Option<&'a [u8]>,
+    labels: &'a HashMap<ScopedIdentifier, (u16, Span)>,
+    other_span: Option<Span>,
+    identifiers: Vec<ScopedIdentifier>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.symbols
+            .get(self.position + offset)
+            .map(|symbol| symbol.node)
+    }
+
+    /// Returns the operator at the current position along with its length in characters.
+    fn peek_operator(&self) -> Option<(Operator, usize)> {
+        match (self.peek(0)?, self.peek(1)) {
+            (b'*', _) => Some((Operator::Multiply, 1)),
+            (b'/', _) => Some((Operator::Divide, 1)),
+            (b'+', _) => Some((Operator::Add, 1)),
+            (b'-', _) => Some((Operator::Subtract, 1)),
+            (b'<', Some(b'<')) => Some((Operator::ShiftLeft, 2)),
+            (b'>', Some(b'>')) => Some((Operator::ShiftRight, 2)),
+            (b'<', Some(b'=')) => Some((Operator::LessOrEqual, 2)),
+            (b'>', Some(b'=')) => Some((Operator::GreaterOrEqual, 2)),
+            (b'<', _) => Some((Operator::Less, 1)),
+            (b'>', _) => Some((Operator::Greater, 1)),
+            (b'=', Some(b'=')) => Some((Operator::Equal, 2)),
+            (b'!', Some(b'=')) => Some((Operator::NotEqual, 2)),
+            (b'&', _) => Some((Operator::And, 1)),
+            (b'|', _) => Some((Operator::Or, 1)),
+            _ => None,
+        }
+    }
+
+    /// Span of the symbols from the `from` index up to, but not including, the current position.
+    fn span_from(&self, from: usize) -> Span {
+        Span::combine(
+            &self.symbols[from].span,
+            &self.symbols[self.position - 1].span,
+        )
+    }
+
+    fn evaluate_binary(&mut self, precedence: usize) -> Result<(i64, Span), Error> {
+        if precedence == PRECEDENCE_LEVELS {
+            return self.evaluate_primary();
+        }
+
+        let start = self.position;
+        let (mut value, _) = self.evaluate_binary(precedence + 1)?;
+        while let Some((operator, length)) = self.peek_operator() {
+            if operator.precedence() != precedence {
+                break;
+            }
+            self.position += length;
+            let (rhs, rhs_span) = self.evaluate_binary(precedence + 1)?;
+            value = match operator {
+                Operator::Multiply => value.saturating_mul(rhs),
+                Operator::Divide => match value.checked_div(rhs) {
+                    Some(value) => value,
+                    None => {
+                        return Err(Error::DivisionByZero {
+                            span: rhs_span.into(),
+                        })
+                    }
+                },
+                Operator::Add => value.saturating_add(rhs),
+                Operator::Subtract => value.saturating_sub(rhs),
+                Operator::ShiftLeft => match rhs {
+                    0..=32 => value.saturating_mul(1 << rhs),
+                    _ => i64::MAX,
+                },
+                Operator::ShiftRight => match rhs {
+                    0..=63 => value >> rhs,
+                    _ => 0,
+                },
+                Operator::Less => (value < rhs) as i64,
+                Operator::LessOrEqual => (value <= rhs) as i64,
+                Operator::Greater => (value > rhs) as i64,
+                Operator::GreaterOrEqual => (value >= rhs) as i64,
+                Operator::Equal => (value == rhs) as i64,
+                Operator::NotEqual => (value != rhs) as i64,
+                Operator::And => value & rhs,
+                Operator::Or => value | rhs,
+            };
+        }
+
+        Ok((value, self.span_from(start)))
+    }
+
+    fn evaluate_primary(&mut self) -> Result<(i64, Span), Error> {
+        let start = self.position;
+
+        if let Some(value) = self.evaluate_label()? {
+            return Ok((value, self.span_from(start)));
+        }
+
+        match self.peek(0) {
+            Some(b'<') => {
+                self.position += 1;
+                let (value, _) = self.evaluate_primary()?;
+                return Ok((value & 0xff, self.span_from(start)));
+            }
+            Some(b'>') => {
+                self.position += 1;
+                let (value, _) = self.evaluate_primary()?;
+                return Ok(((value >> 8) & 0xff, self.span_from(start)));
+            }
+            _ => (),
+        }
+
+        let length = self.symbols[start..]
+            .iter()
+            .position(|symbol| is_operator(symbol.node))
+            .unwrap_or(self.symbols.len() - start);
+        if length == 0 {
+            return Err(Error::OperandExpected {
+                span: match self.symbols.get(start) {
+                    Some(symbol) => symbol.span.into(),
+                    None => self.symbols[start - 1].span.into(),
+                },
+            });
+        }
+
+        let operand = &self.symbols[start..start + length];
+        self.position += length;
+        if length <= 4 {
+            if let Some(value) = parse_hex_digits(operand) {
+                return Ok((value, self.span_from(start)));
+            }
+        }
+
+        Err(Error::LabelUndefined {
+            name: String::from_utf8_lossy(&to_string(operand)).into_owned(),
+            span: self.span_from(start).into(),
+        })
+    }
+
+    /// Evaluates the longest defined label at the current position that is followed by an
+    /// operator or by the end of the expression.
+    fn evaluate_label(&mut self) -> Result<Option<i64>, Error> {
+        let rest = &self.symbols[self.position..];
+        for length in (1..=rest.len()).rev() {
+            if length < rest.len() && !is_operator(rest[length].node) {
+                continue;
+            }
+            if let Some(identifier) = self.identifier(&to_string(&rest[..length])) {
+                if let Some((identifier, (address, span))) =
+                    lookup(self.labels, &identifier, self.namespace)
+                {
+                    if identifier.is_private_to_other(self.namespace) {
+                        return Err(Error::LabelPrivate {
+                            name: identifier.to_string(),
+                            span: Span::combine(&rest[0].span, &rest[length - 1].span).into(),
+                            other_span: (*span).into(),
+                        });
+                    }
+                    self.position += length;
+                    self.other_span.get_or_insert(*span);
+                    self.identifiers.push(identifier.clone());
+                    return Ok(Some(*address as i64));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn identifier(&self, name: &[u8]) -> Option<ScopedIdentifier> {
+        if let [b'%', local @ ..] = name {
+            if local.is_empty() || local.contains(&b'/') {
+                return None;
+            }
+            return Some(ScopedIdentifier::Local(local.to_vec(), self.expansion?));
+        }
+        let depth = name.iter().take_while(|c| **c == b'&').count();
+        if self.scope.len() < depth {
+            return None;
+        }
+        let mut path = self.scope[..depth].to_vec();
+        path.extend(name[depth..].split(|c| *c == b'/').map(<[u8]>::to_vec));
+        if path.iter().any(Vec::is_empty) {
+            return None;
+        }
+        Some(ScopedIdentifier::from_path(&path))
+    }
+}
+
+/// Looks up the definition of the label that an identifier refers to from a module. Unless the
+/// identifier is qualified, a label defined in the module takes precedence over a global label.
+pub(crate) fn lookup<'a>(
+    labels: &'a HashMap<ScopedIdentifier, (u16, Span)>,
+    identifier: &ScopedIdentifier,
+    namespace: Option<&[u8]>,
+) -> Option<(&'a ScopedIdentifier, &'a (u16, Span))> {
+    namespace
+        .and_then(|module| identifier.qualified(module))
+        .and_then(|qualified| labels.get_key_value(&qualified))
+        .or_else(|| labels.get_key_value(identifier))
+}
+
+fn parse_hex_digits(symbols: &[Spanned<u8>]) -> Option<i64> {
+    symbols
+        .iter()
+        .try_fold(0, |value, symbol| match symbol.node {
+            ch @ b'0'..=b'9' => Some((value << 4) + (ch - b'0') as i64),
+            ch @ b'a'..=b'f' => Some((value << 4) + (ch - b'a' + 10) as i64),
+            _ => None,
+        })
+}
+
+fn to_string(symbols: &[Spanned<u8>]) -> Vec<u8> {
+    symbols.iter().map(|Spanned { node: ch, .. }| *ch).collect()
+}
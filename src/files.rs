@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+
+/// Identifier of a file in a [`Files`] collection.
+pub type FileId = usize;
+
+/// Collection of all source files that take part in the assembly of a program.
+///
+/// The file containing the program is added by the caller, and every file included from it is
+/// added during the assembly. The identifiers of the files are used in the [`FileSpan`]s of the
+/// reported errors and warnings.
+///
+/// [`FileSpan`]: crate::FileSpan
+#[derive(Debug, Default)]
+pub struct Files {
+    files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl Files {
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    /// Adds a file with the given path and contents to the collection and returns its
+    /// identifier.
+    pub fn add(&mut self, path: impl Into<PathBuf>, source: impl Into<Vec<u8>>) -> FileId {
+        self.files.push((path.into(), source.into()));
+        self.files.len() - 1
+    }
+
+    /// Returns the path of the file with the given identifier.
+    pub fn path(&self, file_id: FileId) -> &Path {
+        &self.files[file_id].0
+    }
+
+    /// Returns the contents of the file with the given identifier.
+    pub fn source(&self, file_id: FileId) -> &[u8] {
+        &self.files[file_id].1
+    }
+
+    /// Returns an iterator over the identifiers of all files in the collection.
+    pub fn ids(&self) -> impl Iterator<Item = FileId> {
+        0..self.files.len()
+    }
+}
@@ -1,19 +1,31 @@
 mod anomalies;
+mod builtins;
 pub(crate) mod emitter;
+mod expression;
+mod files;
 mod instruction;
+pub(crate) mod loader;
+mod options;
 pub(crate) mod scanner;
 mod span;
+mod symbol;
 mod token;
 pub(crate) mod tokenizer;
 mod traits;
 pub(crate) mod walker;
 
 pub use anomalies::{Error, Warning};
+pub use files::{FileId, Files};
 pub(crate) use instruction::{Instruction, InstructionKind};
+pub use options::Options;
+pub use span::FileSpan;
 pub(crate) use span::{Location, Span, Spanned, Spanning};
-pub(crate) use token::{Identifier, Token};
+use std::rc::Rc;
+pub use symbol::Symbol;
+pub(crate) use token::{Direction, Directive, Identifier, Operand, Token};
 use tokenizer::Word;
-pub(crate) use traits::{Stockpile, UnzipCollect};
+pub(crate) use traits::Stockpile;
+use walker::Expansion;
 
 /// Assembles an Uxn binary from a string representing an Uxntal program.
 ///
@@ -25,6 +37,11 @@ pub(crate) use traits::{Stockpile, UnzipCollect};
 ///   any [`Warning`]s that may have also been generated. The `Vec` containing the errors is always
 ///   non-empty.
 ///
+/// The program has no file of its own, so the files it includes with the `~` rune are looked up
+/// relative to the current working directory of the process. To look them up relative to the
+/// directory of the program, or in other directories, use [`assemble_with`] along with
+/// [`Options::with_include_path`].
+///
 /// # Example
 ///
 /// ```rust
@@ -33,35 +50,87 @@ pub(crate) use traits::{Stockpile, UnzipCollect};
 /// assert_eq!(binary, [0x80, 0x02, 0x80, 0x03, 0x18]);
 /// ```
 pub fn assemble(source: &[u8]) -> Result<(Vec<u8>, Vec<Warning>), (Vec<Error>, Vec<Warning>)> {
+    let mut files = Files::new();
+    let file_id = files.add("", source);
+
+    assemble_with(&mut files, file_id, &Options::new())
+        .map(|(binary, _, warnings)| (binary, warnings))
+}
+
+/// Assembles an Uxn binary from a file in a [`Files`] collection, using the provided [`Options`].
+///
+/// Every file included from the assembled file is added to `files`, so that the [`FileSpan`]s of
+/// the returned [`Error`]s and [`Warning`]s can point to it. Included files are searched for
+/// relative to the directory of the including file first, and then in each of the include paths
+/// of the `options`.
+///
+/// The result has the same meaning as the result of the [`assemble`] function, except that
+/// a valid program is returned along with its symbol table &mdash; a list of every label and its
+/// address, sorted by the address.
+///
+/// # Example
+///
+/// ```rust
+/// let mut files = ruxnasm::Files::new();
+/// let file_id = files.add("main.tal", b"|0100 @Main #02 #03 ADD".to_vec());
+/// let (binary, symbols, _) =
+///     ruxnasm::assemble_with(&mut files, file_id, &ruxnasm::Options::new()).unwrap();
+///
+/// assert_eq!(binary, [0x80, 0x02, 0x80, 0x03, 0x18]);
+/// assert_eq!(symbols[0].name, "Main");
+/// assert_eq!(symbols[0].address, 0x0100);
+/// ```
+pub fn assemble_with(
+    files: &mut Files,
+    file_id: FileId,
+    options: &Options,
+) -> Result<(Vec<u8>, Vec<Symbol>, Vec<Warning>), (Vec<Error>, Vec<Warning>)> {
     let mut warnings = Vec::new();
 
-    let words = scanner::Scanner::new(source)
-        .unzip_collect()
+    let mut loader = loader::Loader::new(files, &options.include_paths);
+    let words = loader
+        .load(file_id)
         .stockpile(&mut warnings)
         .map_err(|errors| (errors, warnings.clone()))?;
 
-    let mut walker = walker::Walker::new();
-    let words: Vec<&Word> = words.iter().collect();
-    let mut stack: Vec<Vec<&Word>> = vec![words];
-    let mut chain: Vec<(Vec<u8>, Span)> = Vec::new();
+    let environment = builtins::Environment {
+        file_names: loader.file_names(),
+        main_file: file_id,
+        timestamp: options.timestamp.unwrap_or_else(builtins::timestamp),
+    };
+    let mut walker = walker::Walker::new(&options.definitions, environment);
+    let words: Vec<Rc<Word>> = words.into_iter().map(Rc::new).collect();
+    let mut stack: Vec<Vec<Rc<Word>>> = vec![words];
+    // Names of the macros that are being expanded, `None` for repeat blocks and included files,
+    // along with the spans of their invocations and whether they are included files.
+    let mut chain: Vec<(Option<Vec<u8>>, Span, bool)> = Vec::new();
 
     while let Some(top) = stack.pop() {
         match walker.walk(&top) {
-            Some((macro_words, macro_name, invoke_span, previous_words)) => {
+            Some((Expansion::Block(macro_words, macro_name), invoke_span, previous_words)) => {
                 stack.push(previous_words);
                 stack.push(macro_words);
-                if let Some(position) = chain.iter().position(|(n, _)| *n == macro_name) {
+                let position = match &macro_name {
+                    Some(_) => chain.iter().position(|(n, _, _)| *n == macro_name),
+                    None => None,
+                };
+                if let Some(position) = position {
                     let mut actual_chain = vec![(macro_name.clone(), invoke_span)];
-                    actual_chain.extend(chain.iter().skip(position + 1).cloned());
+                    actual_chain.extend(
+                        chain
+                            .iter()
+                            .skip(position + 1)
+                            .map(|(macro_name, macro_span, _)| (macro_name.clone(), *macro_span)),
+                    );
                     return Err((
                         vec![Error::RecursiveMacro {
                             chain: actual_chain
                                 .into_iter()
-                                .map(|(macro_name, macro_span)| {
-                                    (
-                                        String::from_utf8_lossy(&macro_name).into_owned(),
+                                .filter_map(|(macro_name, macro_span)| {
+                                    Some((
+                                        String::from_utf8_lossy(&macro_name?).into_owned(),
                                         macro_span.into(),
-                                    )
+                                    ))
                                 })
                                 .collect(),
                             span: chain[position].1.into(),
@@ -69,15 +138,36 @@ pub fn assemble(source: &[u8]) -> Result<(Vec<u8>, Vec<Warning>), (Vec<Error>, V
                         warnings,
                     ));
                 } else {
-                    chain.push((macro_name, invoke_span));
+                    chain.push((macro_name, invoke_span, false));
                 }
             }
+            Some((Expansion::File(path), include_span, previous_words)) => {
+                let included_words = loader.include(include_span.from.file_id, &path, include_span);
+                walker.set_file_names(loader.file_names());
+                stack.push(previous_words);
+                stack.push(included_words.into_iter().map(Rc::new).collect());
+                chain.push((None, include_span, true));
+            }
+            Some((Expansion::BinaryFile(path), include_span, mut previous_words)) => {
+                if let Some(word) =
+                    loader.include_binary(include_span.from.file_id, &path, include_span)
+                {
+                    previous_words.insert(0, Rc::new(word));
+                }
+                stack.push(previous_words);
+            }
             None => {
-                chain.pop();
+                if let Some((_, _, true)) = chain.pop() {
+                    loader.leave();
+                }
             }
         }
     }
 
+    if !loader.errors().is_empty() {
+        return Err((loader.errors().to_vec(), warnings));
+    }
+
     let (statements, definitions) = match walker.finalize() {
         Ok((statements, definitions, new_warnings)) => {
             warnings.extend(new_warnings);
@@ -93,10 +183,12 @@ pub fn assemble(source: &[u8]) -> Result<(Vec<u8>, Vec<Warning>), (Vec<Error>, V
     // println!("labels: {:?}", definitions.labels.keys());
     // println!("sublabels: {:?}", definitions.sublabels.keys());
 
+    let symbols = definitions.symbols();
+
     match emitter::emit(statements, definitions) {
         Ok((binary, new_warnings)) => {
             warnings.extend(new_warnings);
-            Ok((binary, warnings))
+            Ok((binary, symbols, warnings))
         }
         Err((errors, new_warnings)) => {
             warnings.extend(new_warnings);
@@ -0,0 +1,326 @@
+use crate::{
+    scanner::Scanner, tokenizer::Word, Directive, Error, FileId, Files, Span, Spanned, Spanning,
+    Token, Warning,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Scans files into words. Included files are only read once the walker reaches their include,
+/// so that the files included in inactive conditional blocks, or in macros that are never
+/// expanded, do not have to exist.
+pub(crate) struct Loader<'a> {
+    files: &'a mut Files,
+    include_paths: &'a [PathBuf],
+    /// Paths of the files that are being walked, the innermost one last.
+    chain: Vec<PathBuf>,
+    errors: Vec<Error>,
+}
+
+impl<'a> Loader<'a> {
+    pub fn new(files: &'a mut Files, include_paths: &'a [PathBuf]) -> Self {
+        Self {
+            files,
+            include_paths,
+            chain: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Scans the file containing the program.
+    pub fn load(
+        &mut self,
+        file_id: FileId,
+    ) -> Result<(Vec<Word>, Vec<Warning>), (Vec<Error>, Vec<Warning>)> {
+        let path = self.files.path(file_id).to_path_buf();
+        self.chain.push(canonicalize(&path));
+        let words = self.scan(file_id);
+
+        if self.errors.is_empty() {
+            Ok((words, Vec::new()))
+        } else {
+            Err((std::mem::take(&mut self.errors), Vec::new()))
+        }
+    }
+
+    /// Reads and scans an included source file. Every include has to be followed by a call to
+    /// [`leave`](Self::leave) once the words of the included file have been walked.
+    pub fn include(&mut self, including_file_id: FileId, path: &[u8], span: Span) -> Vec<Word> {
+        let path_string = String::from_utf8_lossy(path).into_owned();
+
+        let candidate = match self.locate(including_file_id, &path_string) {
+            Some(candidate) => candidate,
+            None => {
+                self.errors.push(Error::FileNotFound {
+                    path: path_string,
+                    span: span.into(),
+                });
+                self.chain.push(PathBuf::new());
+                return Vec::new();
+            }
+        };
+
+        let canonical_path = canonicalize(&candidate);
+        if self.chain.contains(&canonical_path) {
+            self.errors.push(Error::RecursiveInclude {
+                path: path_string,
+                span: span.into(),
+            });
+            self.chain.push(PathBuf::new());
+            return Vec::new();
+        }
+
+        self.chain.push(canonical_path);
+        match fs::read(&candidate) {
+            Ok(source) => {
+                let file_id = self.files.add(candidate, source);
+                self.scan(file_id)
+            }
+            Err(io_error) => {
+                self.errors.push(Error::CouldNotReadFile {
+                    path: path_string,
+                    message: io_error.to_string(),
+                    span: span.into(),
+                });
+                Vec::new()
+            }
+        }
+    }
+
+    /// Marks the innermost included file as walked.
+    pub fn leave(&mut self) {
+        self.chain.pop();
+    }
+
+    /// Reads the bytes of a binary file, or of a slice of it, to splice into the program.
+    pub fn include_binary(
+        &mut self,
+        including_file_id: FileId,
+        text: &[u8],
+        span: Span,
+    ) -> Option<Word> {
+        let (path, offset, length) = split_slice(text);
+        let path_string = String::from_utf8_lossy(path).into_owned();
+
+        let candidate = match self.locate(including_file_id, &path_string) {
+            Some(candidate) => candidate,
+            None => {
+                self.errors.push(Error::FileNotFound {
+                    path: path_string,
+                    span: span.into(),
+                });
+                return None;
+            }
+        };
+
+        let bytes = match fs::read(&candidate) {
+            Ok(bytes) => bytes,
+            Err(io_error) => {
+                self.errors.push(Error::CouldNotReadFile {
+                    path: path_string,
+                    message: io_error.to_string(),
+                    span: span.into(),
+                });
+                return None;
+            }
+        };
+
+        let end = match length {
+            Some(length) => offset.checked_add(length),
+            None => Some(bytes.len()),
+        };
+        match end {
+            Some(end) if offset <= end && end <= bytes.len() => Some(Word::Fine {
+                token: Token::IncludedBinary(bytes[offset..end].to_vec()).spanning(span),
+                warnings: Vec::new(),
+            }),
+            _ => {
+                self.errors.push(Error::BinarySliceOutOfBounds {
+                    path: path_string,
+                    offset,
+                    length,
+                    file_length: bytes.len(),
+                    span: span.into(),
+                });
+                None
+            }
+        }
+    }
+
+    /// Returns the names of the files loaded so far, without their directories, indexed by their
+    /// identifiers.
+    pub fn file_names(&self) -> Vec<String> {
+        self.files
+            .ids()
+            .map(|id| {
+                let path = self.files.path(id);
+                path.file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    /// Returns the errors reported so far.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    fn scan(&mut self, file_id: FileId) -> Vec<Word> {
+        let source = self.files.source(file_id).to_vec();
+        let mut words = Vec::new();
+
+        let mut scanner = Scanner::new(&source, file_id);
+        while let Some(result) = scanner.next() {
+            match result {
+                Ok(Word::Fine {
+                    token:
+                        Spanned {
+                            node: Token::Directive(Directive::IncludeBinary),
+                            span,
+                        },
+                    ..
+                }) => match scanner.next_symbols() {
+                    Some(Ok(symbols)) => words.push(Word::Fine {
+                        token: Token::IncludeBinary(
+                            symbols.iter().map(|symbol| symbol.node).collect(),
+                        )
+                        .spanning(Span::combine(&span, &symbols.last().unwrap().span)),
+                        warnings: Vec::new(),
+                    }),
+                    Some(Err(error)) => self.errors.push(error),
+                    None => self
+                        .errors
+                        .push(Error::FilePathExpected { span: span.into() }),
+                },
+                Ok(Word::Fine {
+                    token:
+                        Spanned {
+                            node: Token::Directive(Directive::Assert),
+                            span,
+                        },
+                    ..
+                }) => match scanner.next_symbols() {
+                    Some(Ok(symbols)) => words.push(Word::Fine {
+                        token: Token::Assertion(symbols.clone())
+                            .spanning(Span::combine(&span, &symbols.last().unwrap().span)),
+                        warnings: Vec::new(),
+                    }),
+                    Some(Err(error)) => self.errors.push(error),
+                    None => self
+                        .errors
+                        .push(Error::AssertionConditionExpected { span: span.into() }),
+                },
+                Ok(word) => words.push(word),
+                Err(error) => self.errors.push(error),
+            }
+        }
+
+        words
+    }
+
+    /// Finds a file relative to the including file, or in one of the include paths.
+    fn locate(&self, including_file_id: FileId, path: &str) -> Option<PathBuf> {
+        let including_directory = self
+            .files
+            .path(including_file_id)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        std::iter::once(&including_directory)
+            .chain(self.include_paths)
+            .map(|directory| directory.join(path))
+            .find(|candidate| candidate.exists())
+    }
+}
+
+/// Splits the optional hexadecimal offset and length off the end of a binary file path, like in
+/// `font.uf2:0100:0080`.
+fn split_slice(text: &[u8]) -> (&[u8], usize, Option<usize>) {
+    match split_hex_suffix(text) {
+        Some((rest, last)) => match split_hex_suffix(rest) {
+            Some((path, offset)) => (path, offset, Some(last)),
+            None => (rest, last, None),
+        },
+        None => (text, 0, None),
+    }
+}
+
+/// Splits a non-empty text off a colon followed by a hexadecimal number.
+fn split_hex_suffix(text: &[u8]) -> Option<(&[u8], usize)> {
+    let position = text.iter().rposition(|ch| *ch == b':')?;
+    let value = parse_hex(&text[position + 1..])?;
+    if position == 0 {
+        return None;
+    }
+    Some((&text[..position], value))
+}
+
+fn parse_hex(digits: &[u8]) -> Option<usize> {
+    if digits.is_empty() || digits.len() > 8 {
+        return None;
+    }
+    usize::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()
+}
+
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assemble, Error};
+
+    fn errors(source: &str) -> Vec<Error> {
+        match assemble(source.as_bytes()) {
+            Ok((binary, _)) => panic!("expected errors, got {:02x?}", binary),
+            Err((errors, _)) => errors,
+        }
+    }
+
+    #[test]
+    fn file_not_found() {
+        assert!(matches!(
+            errors("|0100 ~tests/suite/include/missing.tal")[..],
+            [Error::FileNotFound { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ^incbin tests/suite/incbin/missing.bin")[..],
+            [Error::FileNotFound { .. }]
+        ));
+    }
+
+    #[test]
+    fn recursive_include() {
+        assert!(matches!(
+            errors("|0100 ~tests/suite/include/recursive/a.tal")[..],
+            [Error::RecursiveInclude { .. }]
+        ));
+    }
+
+    #[test]
+    fn binary_file_path_expected() {
+        assert!(matches!(
+            errors("|0100 ^incbin")[..],
+            [Error::FilePathExpected { .. }]
+        ));
+    }
+
+    #[test]
+    fn binary_slice_out_of_bounds() {
+        assert!(matches!(
+            errors("|0100 ^incbin tests/suite/incbin/basic/data.bin:0002:0010")[..],
+            [Error::BinarySliceOutOfBounds { .. }]
+        ));
+    }
+
+    #[test]
+    fn assertion_condition_expected() {
+        assert!(matches!(
+            errors("^assert")[..],
+            [Error::AssertionConditionExpected { .. }]
+        ));
+    }
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+/// Options controlling the assembly of a program.
+///
+/// # Example
+///
+/// ```rust
+/// let options = ruxnasm::Options::new()
+///     .with_include_path("lib")
+///     .with_definition("DEBUG", 0x01);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub(crate) include_paths: Vec<PathBuf>,
+    pub(crate) definitions: Vec<(Vec<u8>, u16)>,
+    pub(crate) timestamp: Option<u64>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory to the list of directories searched for included files. The directories
+    /// are searched in the order they were added, after the directory of the including file.
+    pub fn with_include_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.include_paths.push(path.into());
+        self
+    }
+
+    /// Defines a constant with the given name and value before the assembly of the program, as
+    /// if it was defined at the very beginning of it. The constant can be used in conditional
+    /// assembly directives.
+    pub fn with_definition(mut self, name: impl Into<String>, value: u16) -> Self {
+        self.definitions.push((name.into().into_bytes(), value));
+        self
+    }
+
+    /// Sets the time of the build, in seconds since the Unix epoch, that the `ruxnasm/date`
+    /// symbol is derived from. By default, the time is read from the `SOURCE_DATE_EPOCH`
+    /// environment variable, or from the system clock if the variable is not set.
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+}
@@ -3,8 +3,8 @@ use std::{
     slice::Iter,
 };
 
-pub use crate::anomalies::{Error, Warning};
-use crate::{tokenizer::Word, Location, Span, Spanned, Spanning};
+pub use crate::anomalies::Error;
+use crate::{tokenizer::Word, FileId, Location, Span, Spanned, Spanning};
 
 const WHITESPACES: [u8; 6] = [b' ', b'\t', b'\n', 0x0b, 0x0c, b'\r'];
 const DELIMITERS: [u8; 6] = [b'(', b')', b'[', b']', b'{', b'}'];
@@ -20,24 +20,140 @@ fn is_whitespace(ch: u8) -> bool {
     WHITESPACES.contains(&ch)
 }
 
+/// Returns the number of characters in the `name}` part of a macro parameter placeholder, if the
+/// next characters form one.
+fn placeholder_length(chars: &Peekable<Copied<Iter<u8>>>) -> Option<usize> {
+    let mut chars = chars.clone();
+    let mut length = 0;
+    loop {
+        match chars.next() {
+            Some(b'}') if length > 0 => return Some(length + 1),
+            ch if is_delimiter(ch.as_ref()) => return None,
+            _ => length += 1,
+        }
+    }
+}
+
+/// Returns the number of characters in the `name}` part of a macro parameter placeholder, if the
+/// next characters are an opening brace followed by the rest of a placeholder.
+fn placeholder_length_after_brace(chars: &Peekable<Copied<Iter<u8>>>) -> Option<usize> {
+    let mut chars = chars.clone();
+    match chars.next() {
+        Some(b'{') => placeholder_length(&chars),
+        _ => None,
+    }
+}
+
+/// Returns the number of characters in the rest of a quoted string, up to and including the closing
+/// quote and the modifier after it, if the next characters form one. A quoted string has to be
+/// closed on the same line, and any double quotes in it have to be escaped.
+fn quoted_string_length(chars: &Peekable<Copied<Iter<u8>>>) -> Option<usize> {
+    let mut chars = chars.clone();
+    let mut length = 0;
+    loop {
+        match chars.next() {
+            None | Some(b'\n') => return None,
+            Some(b'\\') => match chars.next() {
+                None | Some(b'\n') => return None,
+                Some(_) => length += 2,
+            },
+            Some(b'"') => {
+                length += 1;
+                if let Some(b'z') | Some(b'p') = chars.peek() {
+                    chars.next();
+                    length += 1;
+                }
+                return match is_delimiter(chars.peek()) {
+                    true => Some(length),
+                    false => None,
+                };
+            }
+            Some(_) => length += 1,
+        }
+    }
+}
+
 pub(crate) struct Scanner<'a> {
     chars: Peekable<Copied<Iter<'a, u8>>>,
     location: Location,
+    /// Number of braces opened since the start of the outermost macro or repeat block body. Macro
+    /// parameter placeholders can only appear in such bodies.
+    body_depth: usize,
+    /// Number of words that can still appear before the opening brace of the body of the macro
+    /// definition or the repeat directive just scanned.
+    words_before_body: Option<usize>,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(input_file_contents: &'a [u8]) -> Self {
+    pub fn new(input_file_contents: &'a [u8], file_id: FileId) -> Self {
         Self {
             chars: input_file_contents.into_iter().copied().peekable(),
-            location: Location { offset: 0 },
+            location: Location { file_id, offset: 0 },
+            body_depth: 0,
+            words_before_body: None,
+        }
+    }
+
+    fn is_in_body(&self) -> bool {
+        self.body_depth > 0
+    }
+
+    /// Keeps track of the macro and repeat block bodies, given the symbols of the next word.
+    fn track_body(&mut self, symbols: &[Spanned<u8>]) {
+        let chars: Vec<u8> = symbols.iter().map(|Spanned { node: ch, .. }| *ch).collect();
+        let words_before_body = self.words_before_body.take();
+        match chars.as_slice() {
+            [b'%', _, ..] => self.words_before_body = Some(0),
+            b"^repeat" => self.words_before_body = Some(2),
+            b"{" if words_before_body.is_some() || self.is_in_body() => self.body_depth += 1,
+            b"?{" | b"!{" if self.is_in_body() => self.body_depth += 1,
+            b"}" if self.is_in_body() => self.body_depth -= 1,
+            _ => self.words_before_body = words_before_body.and_then(|count| count.checked_sub(1)),
+        }
+    }
+
+    /// Takes the next `count` characters into the `symbols` of the current word.
+    fn take(&mut self, count: usize, symbols: &mut Vec<Spanned<u8>>) {
+        for _ in 0..count {
+            let ch = self.chars.next().unwrap();
+            symbols.push(ch.spanning(Span::new(self.location)));
+            self.location += 1;
+        }
+    }
+
+    /// Takes the rest of the current word, up to the next delimiter. Macro parameter placeholders,
+    /// like `{name}`, are a part of a word in a body even though they contain braces.
+    fn take_word(&mut self, symbols: &mut Vec<Spanned<u8>>) {
+        loop {
+            if !is_delimiter(self.chars.peek()) {
+                self.take(1, symbols);
+            } else if let Some(length) = self.placeholder_length_after_brace() {
+                self.take(length + 1, symbols);
+            } else {
+                break;
+            }
         }
     }
 }
 
 impl<'a> Iterator for Scanner<'a> {
-    type Item = Result<(Word, Option<Warning>), Error>;
+    type Item = Result<Word, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_symbols()?;
+        Some(result.map(|symbols| {
+            if self.is_in_body() {
+                Word::with_placeholders(&symbols)
+            } else {
+                Word::new(&symbols)
+            }
+        }))
+    }
+}
+
+impl<'a> Scanner<'a> {
+    /// Scans the next word, without tokenizing it.
+    pub fn next_symbols(&mut self) -> Option<Result<Vec<Spanned<u8>>, Error>> {
         let ch = 'whitespace: loop {
             match self.chars.next() {
                 Some(ch) if is_whitespace(ch) => {
@@ -86,48 +202,41 @@ impl<'a> Iterator for Scanner<'a> {
         let mut symbols: Vec<Spanned<u8>> = Vec::new();
         symbols.push((ch).spanning(Span::new(self.location)));
         self.location += 1;
-        let mut ignored_start: Option<Location> = None;
 
         // TODO: Refactor the string scanning
-        if ch == b'"' || ch == b'\'' {
+        if ch == b'"' && quoted_string_length(&self.chars).is_some() {
+            // Quoted strings can contain whitespace.
+            let length = quoted_string_length(&self.chars).unwrap();
+            self.take(length, &mut symbols);
+        } else if ch == b'"' || ch == b'\'' {
             while self.chars.peek().is_some() && !is_whitespace(*self.chars.peek().unwrap()) {
-                let ch = self.chars.next().unwrap();
-                if symbols.len() < 64 {
-                    symbols.push(ch.spanning(Span::new(self.location)));
-                } else {
-                    if ignored_start.is_none() {
-                        ignored_start = Some(self.location);
-                    }
-                }
-                self.location += 1;
+                self.take(1, &mut symbols);
             }
+        } else if ch == b'{' && self.is_in_body() && placeholder_length(&self.chars).is_some() {
+            let length = placeholder_length(&self.chars).unwrap();
+            self.take(length, &mut symbols);
+            self.take_word(&mut symbols);
+        } else if (ch == b'?' || ch == b'!')
+            && self.chars.peek() == Some(&b'{')
+            && self.placeholder_length_after_brace().is_none()
+        {
+            // The opening brace of a conditional or an unconditional lambda belongs to the rune.
+            self.take(1, &mut symbols);
         } else {
-            while !is_delimiter(self.chars.peek()) {
-                let ch = self.chars.next().unwrap();
-                if symbols.len() < 64 {
-                    symbols.push(ch.spanning(Span::new(self.location)));
-                } else {
-                    if ignored_start.is_none() {
-                        ignored_start = Some(self.location);
-                    }
-                }
-                self.location += 1;
-            }
+            self.take_word(&mut symbols);
         }
 
-        let word = Word::new(&symbols);
+        self.track_body(&symbols);
+        Some(Ok(symbols))
+    }
 
-        if let Some(ignored_location) = ignored_start {
-            let warning = Warning::TokenTrimmed {
-                span: Span {
-                    from: ignored_location,
-                    to: self.location,
-                }
-                .into(),
-            };
-            Some(Ok((word, Some(warning))))
+    /// Returns the number of characters in a macro parameter placeholder, without the opening
+    /// brace, if the next characters form one in a body.
+    fn placeholder_length_after_brace(&self) -> Option<usize> {
+        if self.is_in_body() {
+            placeholder_length_after_brace(&self.chars)
         } else {
-            Some(Ok((word, None)))
+            None
         }
     }
 }
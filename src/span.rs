@@ -1,6 +1,7 @@
 use crate::{
     instruction::Instruction,
     token::{Identifier, Statement, Token},
+    FileId,
 };
 use std::{
     fmt,
@@ -9,6 +10,7 @@ use std::{
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct Location {
+    pub(crate) file_id: FileId,
     pub(crate) offset: usize,
 }
 
@@ -17,6 +19,7 @@ impl Add<usize> for Location {
 
     fn add(self, rhs: usize) -> Self::Output {
         Self {
+            file_id: self.file_id,
             offset: self.offset + rhs,
         }
     }
@@ -42,7 +45,13 @@ impl Span {
         }
     }
 
+    /// Combines two spans into one covering both of them. Spans from different files cannot
+    /// be combined, in which case the `start` span is returned.
     pub(crate) fn combine(start: &Span, end: &Span) -> Self {
+        if start.from.file_id != end.to.file_id {
+            return *start;
+        }
+
         Self {
             from: start.from,
             to: end.to,
@@ -50,11 +59,24 @@ impl Span {
     }
 }
 
-impl From<Span> for Range<usize> {
+/// Span of a fragment of a source file, used to point at the source of an [`Error`] or a
+/// [`Warning`].
+///
+/// [`Error`]: crate::Error
+/// [`Warning`]: crate::Warning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSpan {
+    /// Identifier of the file in the [`Files`](crate::Files) collection.
+    pub file_id: FileId,
+    /// Range of bytes in the file.
+    pub range: Range<usize>,
+}
+
+impl From<Span> for FileSpan {
     fn from(span: Span) -> Self {
         Self {
-            start: span.from.offset,
-            end: span.to.offset,
+            file_id: span.from.file_id,
+            range: span.from.offset..span.to.offset,
         }
     }
 }
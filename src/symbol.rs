@@ -0,0 +1,24 @@
+/// A named address in an assembled binary, coming from a label definition.
+///
+/// # Example
+///
+/// ```rust
+/// let mut files = ruxnasm::Files::new();
+/// let source = b"|0000 @Zero $1 |0100 @Main &loop ,&loop JMP".to_vec();
+/// let file_id = files.add("main.tal", source);
+/// let (_, symbols, _) =
+///     ruxnasm::assemble_with(&mut files, file_id, &ruxnasm::Options::new()).unwrap();
+/// let symbols: Vec<(&str, u16)> = symbols
+///     .iter()
+///     .map(|symbol| (symbol.name.as_str(), symbol.address))
+///     .collect();
+///
+/// assert_eq!(symbols, [("Zero", 0x0000), ("Main", 0x0100), ("Main/loop", 0x0100)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Full name of the label, e.g. `label` or `label/sublabel`.
+    pub name: String,
+    /// Address of the label.
+    pub address: u16,
+}
@@ -1,33 +1,130 @@
-use super::Instruction;
+use super::{Error, Instruction, Spanned};
 
 #[derive(Debug, Clone)]
 pub(crate) enum Statement {
     Instruction(Instruction),
     PadAbsolute(u16),
     PadRelative(u16),
-    LiteralZeroPageAddress(ScopedIdentifier),
-    LiteralRelativeAddress(ScopedIdentifier),
-    LiteralAbsoluteAddress(ScopedIdentifier),
-    RawAddress(ScopedIdentifier),
+    Fill(u16, u8),
+    LiteralZeroPageAddress(Reference),
+    LiteralRelativeAddress(Reference),
+    LiteralAbsoluteAddress(Reference),
+    RawZeroPageAddress(Reference),
+    RawRelativeAddress(Reference),
+    RawAddress(Reference),
+    ImmediateJump(Reference),
+    ImmediateConditionalJump(Reference),
+    ImmediateCall(Reference),
     LiteralHexByte(u8),
     LiteralHexShort(u16),
+    LiteralByteExpression(Reference),
+    LiteralShortExpression(Reference),
     RawHexByte(u8),
     RawHexShort(u16),
     RawChar(u8),
     RawWord(Vec<u8>),
+    IncludedBinary(Vec<u8>),
+    /// A condition that must hold once all of the labels are defined, along with the message to
+    /// report if it does not.
+    Assertion(Reference, String),
+}
+
+/// A reference to a label, or a compile-time expression over labels, constants and hexadecimal
+/// numbers, that gets resolved once all of the labels are defined.
+#[derive(Debug, Clone)]
+pub(crate) struct Reference {
+    /// The label that the whole operand names, if it is a valid identifier.
+    pub identifier: Option<ScopedIdentifier>,
+    /// Symbols of the operand, evaluated as an expression if the operand is not an identifier.
+    pub expression: Vec<Spanned<u8>>,
+    /// Names of the label and the nested sublabels that sublabels in the expression are relative
+    /// to.
+    pub scope: Vec<Vec<u8>>,
+    /// Macro expansion that local labels in the expression belong to.
+    pub expansion: Option<usize>,
+    /// Module of the file that the reference appears in, whose labels take precedence over
+    /// global labels with the same name.
+    pub namespace: Option<Vec<u8>>,
+}
+
+impl From<ScopedIdentifier> for Reference {
+    fn from(identifier: ScopedIdentifier) -> Self {
+        Self {
+            identifier: Some(identifier),
+            expression: Vec::new(),
+            scope: Vec::new(),
+            expansion: None,
+            namespace: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum ScopedIdentifier {
     Label(Vec<u8>),
-    Sublabel(Vec<u8>, Vec<u8>),
+    /// A label, followed by the path of nested sublabels, e.g. `draw/row/col`.
+    Sublabel(Vec<u8>, Vec<Vec<u8>>),
+    Lambda(usize),
+    /// A label local to the macro expansion with the given id.
+    Local(Vec<u8>, usize),
+    /// A reference to the nearest numeric label with the given name in the given direction.
+    Numeric(Vec<u8>, Direction),
 }
 
 impl ScopedIdentifier {
+    /// Creates an identifier from the names of a label and of its nested sublabels.
+    ///
+    /// `path` must not be empty.
+    pub fn from_path(path: &[Vec<u8>]) -> Self {
+        match path {
+            [label] => Self::Label(label.clone()),
+            [label, sublabels @ ..] => Self::Sublabel(label.clone(), sublabels.to_vec()),
+            [] => panic!("empty label path"),
+        }
+    }
+
+    /// Returns whether the name of the label, without the module that it is qualified with,
+    /// starts with a capital letter.
     pub fn is_captital(&self) -> bool {
         match self {
-            Self::Label(name) => name.iter().next().unwrap().is_ascii_uppercase(),
-            Self::Sublabel(name, _) => name.iter().next().unwrap().is_ascii_uppercase(),
+            Self::Label(name) | Self::Sublabel(name, _) => {
+                let start = qualified_name_split(name).map_or(0, |index| index + 2);
+                name[start..].first().map_or(false, u8::is_ascii_uppercase)
+            }
+            Self::Lambda(_) | Self::Local(..) | Self::Numeric(..) => false,
+        }
+    }
+
+    /// Returns the module that the label is qualified with, e.g. `audio` for `audio::Init`.
+    pub fn module(&self) -> Option<&[u8]> {
+        match self {
+            Self::Label(name) | Self::Sublabel(name, _) => {
+                qualified_name_split(name).map(|index| &name[..index])
+            }
+            Self::Lambda(_) | Self::Local(..) | Self::Numeric(..) => None,
+        }
+    }
+
+    /// Returns the identifier qualified with the given module, unless it already is qualified
+    /// with one, or it is not a label nor a sublabel.
+    pub fn qualified(&self, module: &[u8]) -> Option<Self> {
+        let qualify = |name: &Vec<u8>| [module, b"::", name].concat();
+        match self {
+            _ if self.module().is_some() => None,
+            Self::Label(name) => Some(Self::Label(qualify(name))),
+            Self::Sublabel(name, sublabels) => {
+                Some(Self::Sublabel(qualify(name), sublabels.clone()))
+            }
+            Self::Lambda(_) | Self::Local(..) | Self::Numeric(..) => None,
+        }
+    }
+
+    /// Returns whether the label can not be referenced from the given module, because it is
+    /// private to another one. Labels in modules are exported if their names are capitalized.
+    pub fn is_private_to_other(&self, namespace: Option<&[u8]>) -> bool {
+        match self.module() {
+            Some(module) => namespace != Some(module) && !self.is_captital(),
+            None => false,
         }
     }
 }
@@ -36,45 +133,136 @@ impl ToString for ScopedIdentifier {
     fn to_string(&self) -> String {
         match self {
             Self::Label(name) => String::from_utf8_lossy(name).into_owned(),
-            Self::Sublabel(label_name, sublabel_name) => {
-                format!(
-                    "{}/{}",
-                    String::from_utf8_lossy(label_name),
-                    String::from_utf8_lossy(sublabel_name)
-                )
+            Self::Sublabel(label_name, sublabel_names) => {
+                let mut name = String::from_utf8_lossy(label_name).into_owned();
+                for sublabel_name in sublabel_names {
+                    name.push('/');
+                    name.push_str(&String::from_utf8_lossy(sublabel_name));
+                }
+                name
             }
+            Self::Lambda(id) => format!("λ{:02x}", id),
+            Self::Local(name, id) => format!("%{}~{:02x}", String::from_utf8_lossy(name), id),
+            Self::Numeric(name, direction) => format!(
+                "{}{}",
+                String::from_utf8_lossy(name),
+                match direction {
+                    Direction::Backward => 'b',
+                    Direction::Forward => 'f',
+                }
+            ),
         }
     }
 }
 
+/// Returns the index of the `::` that separates the module from the name of a qualified label.
+fn qualified_name_split(name: &[u8]) -> Option<usize> {
+    name.windows(2).position(|pair| pair == b"::")
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum Token {
     OpeningBracket,
     ClosingBracket,
     OpeningBrace,
+    OpeningJumpBrace,
+    OpeningConditionalJumpBrace,
     ClosingBrace,
     Instruction(Instruction),
-    MacroDefine(Vec<u8>),
-    MacroInvoke(Vec<u8>),
+    MacroDefine(Vec<u8>, Vec<Vec<u8>>),
+    MacroInvoke(Vec<u8>, Vec<Vec<Spanned<u8>>>),
+    Template(Vec<Spanned<u8>>),
     PadAbsolute(u16),
     PadRelative(u16),
+    /// An absolute pad to the value of a label or an expression, along with the error to report
+    /// instead of the label being undefined, if there is one.
+    PadAbsoluteExpression(Operand, Option<Error>),
+    /// A relative pad by the value of a label or an expression, along with the error to report
+    /// instead of the label being undefined, if there is one.
+    PadRelativeExpression(Operand, Option<Error>),
     LabelDefine(Vec<u8>),
-    SublabelDefine(Vec<u8>),
-    LiteralZeroPageAddress(Identifier),
-    LiteralRelativeAddress(Identifier),
-    LiteralAbsoluteAddress(Identifier),
-    RawAddress(Identifier),
+    /// A sublabel definition, nested at the depth given by the number of ampersands.
+    SublabelDefine(usize, Vec<u8>),
+    /// A definition of a label local to a macro expansion, like `@%loop`.
+    LocalLabelDefine(Vec<u8>),
+    /// A definition of a reusable numeric label, like `&1`.
+    NumericLabelDefine(Vec<u8>),
+    LiteralZeroPageAddress(Operand),
+    LiteralRelativeAddress(Operand),
+    LiteralAbsoluteAddress(Operand),
+    RawZeroPageAddress(Operand),
+    RawRelativeAddress(Operand),
+    RawAddress(Operand),
+    ImmediateJump(Operand),
+    ImmediateConditionalJump(Operand),
     LiteralHexByte(u8),
     LiteralHexShort(u16),
+    LiteralByteExpression(Operand),
+    LiteralShortExpression(Operand),
     RawHexByte(u8),
     RawHexShort(u16),
     RawChar(u8),
     RawWord(Vec<u8>),
+    Include(Vec<u8>),
+    /// The path of a binary file to include, with an optional offset and length, which is only
+    /// read once the include is walked.
+    IncludeBinary(Vec<u8>),
+    IncludedBinary(Vec<u8>),
+    /// The condition of an assertion directive.
+    Assertion(Vec<Spanned<u8>>),
+    Directive(Directive),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Directive {
+    If,
+    IfDefined,
+    IfNotDefined,
+    Else,
+    EndIf,
+    Constant,
+    Align,
+    Fill,
+    IncludeBinary,
+    Section,
+    Allocate,
+    Assert,
+    Error,
+    Warning,
+    Repeat,
+    When,
+    Otherwise,
+    End,
+    While,
+    Do,
+    Loop,
+    Module,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) enum Identifier {
     Label(Vec<u8>),
-    Sublabel(Vec<u8>),
-    Path(Vec<u8>, Vec<u8>),
+    /// A path relative to the current scope, at the depth given by the number of ampersands.
+    Sublabel(usize, Vec<Vec<u8>>),
+    Path(Vec<u8>, Vec<Vec<u8>>),
+    /// A label local to the current macro expansion, like `%loop`.
+    Local(Vec<u8>),
+    /// A reference to the nearest numeric label before or after it, like `1b` or `1f`.
+    Numeric(Vec<u8>, Direction),
+}
+
+/// The direction in which a reference to a numeric label looks for its definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Direction {
+    Backward,
+    Forward,
+}
+
+/// The part of an address or a literal token after its rune.
+#[derive(Debug, Clone)]
+pub(crate) struct Operand {
+    /// The identifier, unless the operand is an expression enclosed in backticks.
+    pub identifier: Option<Identifier>,
+    /// Symbols of the operand, without the backticks around an expression.
+    pub symbols: Vec<Spanned<u8>>,
 }
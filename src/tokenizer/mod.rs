@@ -1,10 +1,12 @@
-use super::{Identifier, Token};
+use super::{Direction, Directive, Identifier, Operand, Token};
 use super::{Span, Spanned, Spanning};
 use crate::anomalies::{Error, Warning};
 use crate::{Instruction, InstructionKind};
 use std::fmt;
 
 mod hex_number;
+mod number;
+mod string;
 
 #[derive(Clone)]
 pub(crate) enum Word {
@@ -24,7 +26,8 @@ impl Word {
             const WHITESPACES: [u8; 6] = [b' ', b'\t', b'\n', 0x0b, 0x0c, b'\r'];
 
             let chars: Vec<u8> = symbols.iter().map(|Spanned { node: ch, .. }| *ch).collect();
-            WHITESPACES.iter().all(|ch| !chars.contains(ch))
+            // Quoted strings are the only words that can contain whitespace.
+            chars.first() == Some(&b'"') || WHITESPACES.iter().all(|ch| !chars.contains(ch))
         });
 
         match tokenize(symbols) {
@@ -37,6 +40,71 @@ impl Word {
     }
 }
 
+impl Word {
+    /// Substitutes the macro parameter placeholders in a word with the corresponding arguments.
+    /// Placeholders of parameters other than the given ones are left as they are.
+    pub(crate) fn substitute(
+        &self,
+        parameters: &[Vec<u8>],
+        arguments: &[Vec<Spanned<u8>>],
+    ) -> Self {
+        let symbols = match self {
+            Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::Template(symbols),
+                        ..
+                    },
+                ..
+            } => symbols,
+            _ => return self.clone(),
+        };
+
+        let mut substituted: Vec<Spanned<u8>> = Vec::new();
+        let mut rest = &symbols[..];
+        while let Some((start, end)) = find_placeholder(rest) {
+            substituted.extend_from_slice(&rest[..start]);
+            let name = to_string(&rest[start + 1..end]);
+            match parameters.iter().position(|parameter| *parameter == name) {
+                Some(index) => substituted.extend_from_slice(&arguments[index]),
+                None => substituted.extend_from_slice(&rest[start..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        substituted.extend_from_slice(rest);
+
+        Word::with_placeholders(&substituted)
+    }
+
+    /// Creates a word in the body of a macro or a repeat block, in which a word with a macro
+    /// parameter placeholder is a template for the word substituted at each expansion. Raw words
+    /// and quoted strings are never templates.
+    pub(crate) fn with_placeholders(symbols: &[Spanned<u8>]) -> Self {
+        if symbols[0].node != b'"' && find_placeholder(symbols).is_some() {
+            return Self::Fine {
+                token: Token::Template(symbols.to_vec()).spanning(to_span(symbols).unwrap()),
+                warnings: Vec::new(),
+            };
+        }
+        Self::new(symbols)
+    }
+}
+
+/// Returns the indices of the opening and the closing brace of the first macro parameter
+/// placeholder in a word.
+pub(crate) fn find_placeholder(symbols: &[Spanned<u8>]) -> Option<(usize, usize)> {
+    let start = symbols.iter().position(|symbol| symbol.node == b'{')?;
+    let length = symbols[start + 1..]
+        .iter()
+        .position(|symbol| symbol.node == b'}')?;
+    if length > 0 {
+        Some((start, start + 1 + length))
+    } else {
+        find_placeholder(&symbols[start + 1..])
+            .map(|(start2, end2)| (start + 1 + start2, start + 1 + end2))
+    }
+}
+
 fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Error> {
     match word.first().cloned().unwrap() {
         Spanned { node: b'[', span } => {
@@ -52,46 +120,45 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
             return Ok((Token::ClosingBrace.spanning(span), Vec::new()))
         }
         Spanned { node: b'%', span } => match parse_macro(span, &word[1..]) {
-            Ok(name) => {
+            Ok((name, parameters)) => {
                 return Ok((
-                    Token::MacroDefine(name).spanning(to_span(word).unwrap()),
+                    Token::MacroDefine(name, parameters).spanning(to_span(word).unwrap()),
                     Vec::new(),
                 ));
             }
             Err(err) => Err(err),
         },
-        Spanned { node: b'|', span } => hex_number::parse_hex_number_unconstrained(&word[1..])
-            .map_err(|err| match err {
-                hex_number::Error2::DigitExpected => Error::HexNumberExpected { span: span.into() },
-                hex_number::Error2::DigitInvalid { digit, span } => Error::HexDigitInvalid {
-                    digit,
-                    number: String::from_utf8_lossy(&to_string(&word[1..])).into_owned(),
-                    span: span.into(),
-                },
-                hex_number::Error2::TooLong { length } => Error::HexNumberTooLong {
-                    length,
-                    number: String::from_utf8_lossy(&to_string(&word[1..])).into_owned(),
-                    span: to_span(&word[1..]).unwrap().into(),
-                },
-            })
-            .map(|value| Token::PadAbsolute(value))
-            .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new())),
-        Spanned { node: b'$', span } => hex_number::parse_hex_number_unconstrained(&word[1..])
-            .map_err(|err| match err {
-                hex_number::Error2::DigitExpected => Error::HexNumberExpected { span: span.into() },
-                hex_number::Error2::DigitInvalid { digit, span } => Error::HexDigitInvalid {
-                    digit,
-                    number: String::from_utf8_lossy(&to_string(&word[1..])).into_owned(),
-                    span: span.into(),
-                },
-                hex_number::Error2::TooLong { length } => Error::HexNumberTooLong {
-                    length,
-                    number: String::from_utf8_lossy(&to_string(&word[1..])).into_owned(),
-                    span: to_span(&word[1..]).unwrap().into(),
-                },
-            })
-            .map(|value| Token::PadRelative(value))
-            .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new())),
+        Spanned { node: b'|', span } => parse_pad(
+            span,
+            &word[1..],
+            Token::PadAbsolute,
+            Token::PadAbsoluteExpression,
+        )
+        .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new())),
+        Spanned { node: b'$', span } => parse_pad(
+            span,
+            &word[1..],
+            Token::PadRelative,
+            Token::PadRelativeExpression,
+        )
+        .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new())),
+        Spanned { node: b'@', .. } if word.len() > 1 && word[1].node == b'%' => {
+            let name = &word[2..];
+            if name.is_empty() {
+                Err(Error::LabelExpected {
+                    span: to_span(word).unwrap().into(),
+                })
+            } else if let Some(position) = name.iter().position(|symbol| symbol.node == b'/') {
+                Err(Error::SlashInLabelOrSublabel {
+                    span: name[position].span.into(),
+                })
+            } else {
+                Ok((
+                    Token::LocalLabelDefine(to_string(name)).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                ))
+            }
+        }
         Spanned { node: b'@', span } => {
             if !word[1..].is_empty() {
                 if word[1].node != b'&' {
@@ -119,25 +186,57 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                 Err(Error::LabelExpected { span: span.into() })
             }
         }
-        Spanned { node: b'&', span } => {
-            if !word[1..].is_empty() {
-                if let Some(position) = word[1..]
+        Spanned { node: b'&', .. } if is_numeric_label(&word[1..]) => Ok((
+            Token::NumericLabelDefine(to_string(&word[1..])).spanning(to_span(word).unwrap()),
+            Vec::new(),
+        )),
+        Spanned { node: b'&', .. } => {
+            let depth = word.iter().take_while(|symbol| symbol.node == b'&').count();
+            if !word[depth..].is_empty() {
+                if let Some(position) = word[depth..]
                     .iter()
                     .map(|Spanned { node: ch, .. }| *ch)
                     .position(|c| c == b'/')
                 {
                     Err(Error::SlashInLabelOrSublabel {
-                        span: word[1 + position].span.into(),
+                        span: word[depth + position].span.into(),
                     })
                 } else {
                     Ok((
-                        Token::SublabelDefine(to_string(&word[1..]))
+                        Token::SublabelDefine(depth, to_string(&word[depth..]))
                             .spanning(to_span(word).unwrap()),
                         Vec::new(),
                     ))
                 }
             } else {
-                Err(Error::LabelExpected { span: span.into() })
+                Err(Error::LabelExpected {
+                    span: to_span(word).unwrap().into(),
+                })
+            }
+        }
+        Spanned { node: b'#', .. } if number::is_number(&word[1..]) => {
+            number::parse_number(&word[1..]).map(|number| {
+                let token = match number {
+                    hex_number::HexNumber::Byte(value) => Token::LiteralHexByte(value),
+                    hex_number::HexNumber::Short(value) => Token::LiteralHexShort(value),
+                };
+                (token.spanning(to_span(word).unwrap()), Vec::new())
+            })
+        }
+        Spanned { node: b'#', span } if word.len() > 2 && word[1].node == b'`' => {
+            match parse_expression(span, &word[1..]).unwrap() {
+                Ok(symbols) => {
+                    let operand = Operand {
+                        identifier: None,
+                        symbols,
+                    };
+                    let token = match operand.symbols[0].node {
+                        b'<' | b'>' => Token::LiteralByteExpression(operand),
+                        _ => Token::LiteralShortExpression(operand),
+                    };
+                    Ok((token.spanning(to_span(word).unwrap()), Vec::new()))
+                }
+                Err(err) => Err(err),
             }
         }
         Spanned { node: b'#', span } => match hex_number::parse_hex_number(&word[1..]) {
@@ -166,7 +265,7 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
             }),
         }
         .map(|token| (token.spanning(to_span(word).unwrap()), Vec::new())),
-        Spanned { node: b'.', span } => match parse_identifier(span, &word[1..]) {
+        Spanned { node: b'.', span } => match parse_operand(span, &word[1..]) {
             Ok(name) => {
                 return Ok((
                     Token::LiteralZeroPageAddress(name).spanning(to_span(word).unwrap()),
@@ -175,7 +274,7 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
             }
             Err(err) => Err(err),
         },
-        Spanned { node: b',', span } => match parse_identifier(span, &word[1..]) {
+        Spanned { node: b',', span } => match parse_operand(span, &word[1..]) {
             Ok(name) => {
                 return Ok((
                     Token::LiteralRelativeAddress(name).spanning(to_span(word).unwrap()),
@@ -184,7 +283,7 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
             }
             Err(err) => Err(err),
         },
-        Spanned { node: b';', span } => match parse_identifier(span, &word[1..]) {
+        Spanned { node: b';', span } => match parse_operand(span, &word[1..]) {
             Ok(name) => {
                 return Ok((
                     Token::LiteralAbsoluteAddress(name).spanning(to_span(word).unwrap()),
@@ -193,10 +292,60 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
             }
             Err(err) => Err(err),
         },
-        Spanned { node: b':', span } => match parse_identifier(span, &word[1..]) {
+        Spanned { node: b'-', span } => match parse_operand(span, &word[1..]) {
+            Ok(name) => {
+                return Ok((
+                    Token::RawZeroPageAddress(name).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                ));
+            }
+            Err(err) => Err(err),
+        },
+        Spanned { node: b'_', span } => match parse_operand(span, &word[1..]) {
+            Ok(name) => {
+                return Ok((
+                    Token::RawRelativeAddress(name).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                ));
+            }
+            Err(err) => Err(err),
+        },
+        Spanned { node: b':', span } | Spanned { node: b'=', span } => {
+            match parse_operand(span, &word[1..]) {
+                Ok(name) => {
+                    return Ok((
+                        Token::RawAddress(name).spanning(to_span(word).unwrap()),
+                        Vec::new(),
+                    ));
+                }
+                Err(err) => Err(err),
+            }
+        }
+        Spanned { node: b'!', .. } if to_string(&word[1..]) == b"{" => {
+            return Ok((
+                Token::OpeningJumpBrace.spanning(to_span(word).unwrap()),
+                Vec::new(),
+            ));
+        }
+        Spanned { node: b'?', .. } if to_string(&word[1..]) == b"{" => {
+            return Ok((
+                Token::OpeningConditionalJumpBrace.spanning(to_span(word).unwrap()),
+                Vec::new(),
+            ));
+        }
+        Spanned { node: b'!', span } => match parse_operand(span, &word[1..]) {
+            Ok(name) => {
+                return Ok((
+                    Token::ImmediateJump(name).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                ));
+            }
+            Err(err) => Err(err),
+        },
+        Spanned { node: b'?', span } => match parse_operand(span, &word[1..]) {
             Ok(name) => {
                 return Ok((
-                    Token::RawAddress(name).spanning(to_span(word).unwrap()),
+                    Token::ImmediateConditionalJump(name).spanning(to_span(word).unwrap()),
                     Vec::new(),
                 ));
             }
@@ -219,6 +368,61 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                 }
             }
         }
+        Spanned { node: b'~', span } => {
+            if !word[1..].is_empty() {
+                Ok((
+                    Token::Include(to_string(&word[1..])).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                ))
+            } else {
+                Err(Error::FilePathExpected { span: span.into() })
+            }
+        }
+        Spanned { node: b'^', span } => {
+            let directive = match to_string(&word[1..]).as_slice() {
+                b"if" => Directive::If,
+                b"ifdef" => Directive::IfDefined,
+                b"ifndef" => Directive::IfNotDefined,
+                b"else" => Directive::Else,
+                b"endif" => Directive::EndIf,
+                b"const" => Directive::Constant,
+                b"align" => Directive::Align,
+                b"fill" => Directive::Fill,
+                b"incbin" => Directive::IncludeBinary,
+                b"section" => Directive::Section,
+                b"zp" => Directive::Allocate,
+                b"assert" => Directive::Assert,
+                b"error" => Directive::Error,
+                b"warning" => Directive::Warning,
+                b"repeat" => Directive::Repeat,
+                b"when" => Directive::When,
+                b"otherwise" => Directive::Otherwise,
+                b"end" => Directive::End,
+                b"while" => Directive::While,
+                b"do" => Directive::Do,
+                b"loop" => Directive::Loop,
+                b"module" => Directive::Module,
+                b"" => return Err(Error::DirectiveExpected { span: span.into() }),
+                name => {
+                    return Err(Error::DirectiveUnknown {
+                        name: String::from_utf8_lossy(name).into_owned(),
+                        span: to_span(&word[1..]).unwrap().into(),
+                    })
+                }
+            };
+            Ok((
+                Token::Directive(directive).spanning(to_span(word).unwrap()),
+                Vec::new(),
+            ))
+        }
+        Spanned { node: b'"', .. } if string::is_quoted_string(word) => {
+            string::parse_quoted_string(word).map(|bytes| {
+                (
+                    Token::RawWord(bytes).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                )
+            })
+        }
         Spanned { node: b'"', .. } => {
             return Ok((
                 Token::RawWord(to_string(&word[1..])).spanning(to_span(word).unwrap()),
@@ -242,10 +446,12 @@ fn tokenize(word: &[Spanned<u8>]) -> Result<(Spanned<Token>, Vec<Warning>), Erro
                     new_warnings,
                 ));
             };
-            return Ok((
-                Token::MacroInvoke(to_string(word)).spanning(to_span(word).unwrap()),
-                Vec::new(),
-            ));
+            return parse_macro_invocation(word).map(|(name, arguments)| {
+                (
+                    Token::MacroInvoke(name, arguments).spanning(to_span(word).unwrap()),
+                    Vec::new(),
+                )
+            });
         }
     }
 }
@@ -258,7 +464,117 @@ fn to_span(symbols: &[Spanned<u8>]) -> Option<Span> {
     Some(Span::combine(&symbols.first()?.span, &symbols.last()?.span))
 }
 
-fn parse_macro(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Vec<u8>, Error> {
+/// Parses the operand of a pad rune, which is either a hexadecimal number, or a label or an
+/// expression that the walker resolves to the value to pad with.
+///
+/// An operand that starts with a hexadecimal digit, but is not a hexadecimal number, is most
+/// likely a mistyped number. It is only taken for a label if a label of that name gets defined,
+/// and otherwise reported as an invalid hexadecimal digit.
+fn parse_pad(
+    rune_span: Span,
+    symbols: &[Spanned<u8>],
+    value: fn(u16) -> Token,
+    operand: fn(Operand, Option<Error>) -> Token,
+) -> Result<Token, Error> {
+    match hex_number::parse_hex_number_unconstrained(symbols) {
+        Ok(number) => Ok(value(number)),
+        Err(hex_number::Error2::DigitExpected) => Err(Error::HexNumberExpected {
+            span: rune_span.into(),
+        }),
+        Err(hex_number::Error2::DigitInvalid { digit, span }) => {
+            if symbols[0].node.is_ascii_digit() || (b'a'..=b'f').contains(&symbols[0].node) {
+                let error = Error::HexDigitInvalid {
+                    digit,
+                    number: String::from_utf8_lossy(&to_string(symbols)).into_owned(),
+                    span: span.into(),
+                };
+                match parse_operand(rune_span, symbols) {
+                    Ok(parsed_operand) => Ok(operand(parsed_operand, Some(error))),
+                    Err(_) => Err(error),
+                }
+            } else {
+                parse_operand(rune_span, symbols)
+                    .map(|parsed_operand| operand(parsed_operand, None))
+            }
+        }
+        Err(hex_number::Error2::TooLong { length }) => Err(Error::HexNumberTooLong {
+            length,
+            number: String::from_utf8_lossy(&to_string(symbols)).into_owned(),
+            span: to_span(symbols).unwrap().into(),
+        }),
+    }
+}
+
+/// Parses the name of a macro, followed by the names of its parameters, each preceded by a colon,
+/// e.g. `print:label:length`.
+fn parse_macro(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error> {
+    let mut parts = symbols.split(|Spanned { node: ch, .. }| *ch == b':');
+    let name = parse_macro_name(rune_span, parts.next().unwrap())?;
+
+    let mut parameters: Vec<Spanned<Vec<u8>>> = Vec::new();
+    let mut colon_index = name.len();
+    for part in parts {
+        if part.is_empty() {
+            return Err(Error::MacroParameterExpected {
+                span: symbols[colon_index].span.into(),
+            });
+        }
+        let parameter = to_string(part).spanning(to_span(part).unwrap());
+        if let Some(other) = parameters.iter().find(|other| other.node == parameter.node) {
+            return Err(Error::MacroParameterDefinedMoreThanOnce {
+                name: String::from_utf8_lossy(&parameter.node).into_owned(),
+                span: parameter.span.into(),
+                other_span: other.span.into(),
+            });
+        }
+        parameters.push(parameter);
+        colon_index += 1 + part.len();
+    }
+
+    Ok((
+        name,
+        parameters
+            .into_iter()
+            .map(|Spanned { node, .. }| node)
+            .collect(),
+    ))
+}
+
+/// Parses a macro invocation, i.e. the name of a macro, followed by the arguments, each preceded
+/// by a colon, e.g. `print:hello:05`. Double colons are a part of the name, as in a qualified
+/// label name like `audio::Init`.
+fn parse_macro_invocation(
+    symbols: &[Spanned<u8>],
+) -> Result<(Vec<u8>, Vec<Vec<Spanned<u8>>>), Error> {
+    let mut name_length = 0;
+    while name_length < symbols.len() {
+        match (symbols[name_length].node, symbols.get(name_length + 1)) {
+            (b':', Some(Spanned { node: b':', .. })) => name_length += 2,
+            (b':', _) => break,
+            _ => name_length += 1,
+        }
+    }
+    let name = to_string(&symbols[..name_length]);
+    let parts = symbols[name_length..]
+        .split(|Spanned { node: ch, .. }| *ch == b':')
+        .skip(1);
+
+    let mut arguments: Vec<Vec<Spanned<u8>>> = Vec::new();
+    let mut colon_index = name.len();
+    for part in parts {
+        if part.is_empty() {
+            return Err(Error::MacroArgumentExpected {
+                span: symbols[colon_index].span.into(),
+            });
+        }
+        arguments.push(part.to_vec());
+        colon_index += 1 + part.len();
+    }
+
+    Ok((name, arguments))
+}
+
+fn parse_macro_name(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Vec<u8>, Error> {
     if symbols.is_empty() {
         return Err(Error::MacroNameExpected {
             span: rune_span.into(),
@@ -281,6 +597,47 @@ fn parse_macro(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Vec<u8>, Erro
     Ok(to_string(symbols))
 }
 
+/// Parses the operand of an address rune, which is either an identifier, or an expression enclosed
+/// in backticks that is left to be evaluated once all of the labels are defined.
+fn parse_operand(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Operand, Error> {
+    if let Some(expression) = parse_expression(rune_span, symbols) {
+        return expression.map(|symbols| Operand {
+            identifier: None,
+            symbols,
+        });
+    }
+    parse_identifier(rune_span, symbols).map(|identifier| Operand {
+        identifier: Some(identifier),
+        symbols: symbols.to_vec(),
+    })
+}
+
+/// Parses a compile-time expression enclosed in backticks, like in ``;`table+2` ``, returning the
+/// symbols between the backticks. Returns `None` if the symbols do not start with a backtick.
+fn parse_expression(
+    rune_span: Span,
+    symbols: &[Spanned<u8>],
+) -> Option<Result<Vec<Spanned<u8>>, Error>> {
+    let (opening, rest) = symbols.split_first()?;
+    if opening.node != b'`' {
+        return None;
+    }
+    Some(match rest.split_last() {
+        Some((closing, expression)) if closing.node == b'`' => {
+            if expression.is_empty() {
+                Err(Error::OperandExpected {
+                    span: Span::combine(&opening.span, &closing.span).into(),
+                })
+            } else {
+                Ok(expression.to_vec())
+            }
+        }
+        _ => Err(Error::ExpressionNotClosed {
+            span: Span::combine(&rune_span, &symbols.last().unwrap().span).into(),
+        }),
+    })
+}
+
 fn parse_identifier(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Identifier, Error> {
     if symbols.is_empty() {
         return Err(Error::IdentifierExpected {
@@ -288,61 +645,80 @@ fn parse_identifier(rune_span: Span, symbols: &[Spanned<u8>]) -> Result<Identifi
         });
     }
 
-    if let Some(Spanned { node: b'&', span }) = symbols.first() {
-        let rune_span = Span::combine(&rune_span, &span);
-        if symbols[1..].is_empty() {
+    if let [name @ .., Spanned { node: suffix, .. }] = symbols {
+        if is_numeric_label(name) && (*suffix == b'b' || *suffix == b'f') {
+            let direction = match suffix {
+                b'b' => Direction::Backward,
+                _ => Direction::Forward,
+            };
+            return Ok(Identifier::Numeric(to_string(name), direction));
+        }
+    }
+
+    if symbols[0].node == b'%' {
+        let name = &symbols[1..];
+        if name.is_empty() {
+            return Err(Error::LabelExpected {
+                span: Span::combine(&rune_span, &symbols[0].span).into(),
+            });
+        }
+        if let Some(position) = name.iter().position(|symbol| symbol.node == b'/') {
+            return Err(Error::SlashInLabelOrSublabel {
+                span: name[position].span.into(),
+            });
+        }
+        return Ok(Identifier::Local(to_string(name)));
+    }
+
+    let depth = symbols
+        .iter()
+        .take_while(|Spanned { node: ch, .. }| *ch == b'&')
+        .count();
+    if depth > 0 {
+        let rune_span = Span::combine(&rune_span, &symbols[depth - 1].span);
+        if symbols[depth..].is_empty() {
             return Err(Error::SublabelExpected {
                 span: rune_span.into(),
             });
         }
-        return Ok(Identifier::Sublabel(to_string(&symbols[1..])));
+        let path = parse_path(&symbols[depth..], rune_span)?;
+        return Ok(Identifier::Sublabel(depth, path));
     }
 
-    match symbols
-        .iter()
-        .map(|Spanned { node: ch, .. }| *ch)
-        .position(|c| c == b'/')
-    {
-        Some(position) => {
-            if let Some(second_position) = symbols[position + 1..]
-                .iter()
-                .map(|Spanned { node: ch, .. }| *ch)
-                .position(|c| c == b'/')
-            {
-                return Err(Error::MoreThanOneSlashInIdentifier {
-                    span: symbols[position + 1 + second_position].span.into(),
-                });
-            }
+    let mut path = parse_path(symbols, rune_span)?;
+    let label = path.remove(0);
+    if path.is_empty() {
+        Ok(Identifier::Label(label))
+    } else {
+        Ok(Identifier::Path(label, path))
+    }
+}
 
-            let label = {
-                let label_symbols = &symbols[..position];
-                if label_symbols.is_empty() {
-                    return Err(Error::LabelExpected {
-                        span: rune_span.into(),
-                    });
-                }
-                to_string(label_symbols)
-            };
-            let sublabel = {
-                let sublabel_symbols = &symbols[position + 1..];
-                if sublabel_symbols.is_empty() {
-                    return Err(Error::SublabelExpected {
-                        span: symbols[position].span.into(),
-                    });
-                }
-                to_string(sublabel_symbols)
-            };
-            Ok(Identifier::Path(label, sublabel))
-        }
-        None => {
-            if symbols.is_empty() {
-                return Err(Error::LabelExpected {
+/// Returns whether the symbols are a name of a numeric label, i.e. a decimal number.
+fn is_numeric_label(symbols: &[Spanned<u8>]) -> bool {
+    !symbols.is_empty() && symbols.iter().all(|symbol| symbol.node.is_ascii_digit())
+}
+
+/// Splits a path of a label and its nested sublabels, like `draw/row/col`, into the names.
+fn parse_path(symbols: &[Spanned<u8>], rune_span: Span) -> Result<Vec<Vec<u8>>, Error> {
+    let slashes = (0..symbols.len()).filter(|index| symbols[*index].node == b'/');
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut start = 0;
+    for end in slashes.chain(std::iter::once(symbols.len())) {
+        if start == end {
+            return Err(match start {
+                0 => Error::LabelExpected {
                     span: rune_span.into(),
-                });
-            }
-            Ok(Identifier::Label(to_string(symbols)))
+                },
+                _ => Error::SublabelExpected {
+                    span: symbols[start - 1].span.into(),
+                },
+            });
         }
+        path.push(to_string(&symbols[start..end]));
+        start = end + 1;
     }
+    Ok(path)
 }
 
 /// `symbols` must not be empty.
@@ -469,3 +845,151 @@ impl fmt::Debug for Word {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    fn symbols(word: &str) -> Vec<Spanned<u8>> {
+        word.bytes()
+            .enumerate()
+            .map(|(offset, ch)| ch.spanning(Span::new(Location { file_id: 0, offset })))
+            .collect()
+    }
+
+    fn token(word: &str) -> Token {
+        match tokenize(&symbols(word)) {
+            Ok((token, _)) => token.node,
+            Err(error) => panic!("expected a token, got {:?}", error),
+        }
+    }
+
+    fn error(word: &str) -> Error {
+        match tokenize(&symbols(word)) {
+            Ok((token, _)) => panic!("expected an error, got {:?}", token.node),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn directive_expected() {
+        assert!(matches!(error("^"), Error::DirectiveExpected { .. }));
+    }
+
+    #[test]
+    fn directive_unknown() {
+        assert!(matches!(error("^nope"), Error::DirectiveUnknown { .. }));
+    }
+
+    #[test]
+    fn file_path_expected() {
+        assert!(matches!(error("~"), Error::FilePathExpected { .. }));
+    }
+
+    #[test]
+    fn macro_parameter_expected() {
+        assert!(matches!(
+            error("%add:a:"),
+            Error::MacroParameterExpected { .. }
+        ));
+    }
+
+    #[test]
+    fn macro_parameter_defined_more_than_once() {
+        assert!(matches!(
+            error("%add:a:a"),
+            Error::MacroParameterDefinedMoreThanOnce { .. }
+        ));
+    }
+
+    #[test]
+    fn macro_argument_expected() {
+        assert!(matches!(
+            error("add:01:"),
+            Error::MacroArgumentExpected { .. }
+        ));
+    }
+
+    #[test]
+    fn expression_not_closed() {
+        assert!(matches!(
+            error(";`label+2"),
+            Error::ExpressionNotClosed { .. }
+        ));
+    }
+
+    #[test]
+    fn operand_expected() {
+        assert!(matches!(error(";``"), Error::OperandExpected { .. }));
+    }
+
+    #[test]
+    fn number_out_of_range() {
+        assert!(matches!(error("#+256:b"), Error::NumberOutOfRange { .. }));
+        assert!(matches!(error("#-129:b"), Error::NumberOutOfRange { .. }));
+    }
+
+    #[test]
+    fn binary_digit_invalid() {
+        assert!(matches!(
+            error("#%102"),
+            Error::BinaryDigitInvalid { digit: '2', .. }
+        ));
+    }
+
+    #[test]
+    fn decimal_digit_invalid() {
+        assert!(matches!(
+            error("#+1a"),
+            Error::DecimalDigitInvalid { digit: 'a', .. }
+        ));
+    }
+
+    #[test]
+    fn digit_expected() {
+        assert!(matches!(error("#%_"), Error::DigitExpected { .. }));
+    }
+
+    #[test]
+    fn hex_digit_invalid_after_a_base_prefix_of_another_assembler() {
+        assert!(matches!(
+            error("#b1010_0101"),
+            Error::HexDigitInvalid { digit: '_', .. }
+        ));
+    }
+
+    #[test]
+    fn hex_digit_invalid_in_pad() {
+        assert!(matches!(
+            token("|01g0"),
+            Token::PadAbsoluteExpression(_, Some(Error::HexDigitInvalid { digit: 'g', .. }))
+        ));
+        assert!(matches!(
+            token("$1g"),
+            Token::PadRelativeExpression(_, Some(Error::HexDigitInvalid { digit: 'g', .. }))
+        ));
+        assert!(matches!(
+            token("|data"),
+            Token::PadAbsoluteExpression(_, Some(Error::HexDigitInvalid { digit: 't', .. }))
+        ));
+        assert!(matches!(
+            token("|label"),
+            Token::PadAbsoluteExpression(_, None)
+        ));
+    }
+
+    #[test]
+    fn escape_sequence_invalid() {
+        assert!(matches!(
+            error("\"a\\q\""),
+            Error::EscapeSequenceInvalid { .. }
+        ));
+    }
+
+    #[test]
+    fn string_too_long() {
+        let word = format!("\"{}\"p", "a".repeat(0x100));
+        assert!(matches!(error(&word), Error::StringTooLong { .. }));
+    }
+}
@@ -0,0 +1,174 @@
+use super::{hex_number::HexNumber, to_span, to_string, Spanned};
+use crate::anomalies::Error;
+
+/// Size of a number, either given with a size suffix, like `#1:s`, or inferred.
+#[derive(Clone, Copy)]
+enum Size {
+    Byte,
+    Short,
+}
+
+struct Parts<'a> {
+    negative: bool,
+    base: Base,
+    digits: &'a [Spanned<u8>],
+    size: Option<Size>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Base {
+    Binary,
+    Decimal,
+    Hexadecimal,
+    /// A single character, like `'a`.
+    Character,
+}
+
+impl Base {
+    fn is_digit(&self, ch: u8) -> bool {
+        match self {
+            Self::Binary => ch == b'0' || ch == b'1' || ch == b'_',
+            Self::Decimal => ch.is_ascii_digit() || ch == b'_',
+            Self::Hexadecimal => ch.is_ascii_digit() || (b'a'..=b'f').contains(&ch),
+            Self::Character => true,
+        }
+    }
+}
+
+/// Splits the symbols after a literal hex rune into the sign, the base prefix, the digits, and
+/// the size suffix. Returns `None` if the symbols are not a number in one of the extended
+/// syntaxes, in which case they are a plain hexadecimal number or a character.
+///
+/// None of the base prefixes is a hexadecimal digit, so a plain hexadecimal number is never
+/// mistaken for a number in another base.
+fn split(symbols: &[Spanned<u8>]) -> Option<Parts<'_>> {
+    let (negative, rest) = match symbols.first()?.node {
+        b'-' if symbols.len() > 1 => (true, &symbols[1..]),
+        _ => (false, symbols),
+    };
+    let (rest, size) = match to_string(rest).as_slice() {
+        [.., b':', b'b'] if rest.len() > 2 => (&rest[..rest.len() - 2], Some(Size::Byte)),
+        [.., b':', b's'] if rest.len() > 2 => (&rest[..rest.len() - 2], Some(Size::Short)),
+        _ => (rest, None),
+    };
+    let (base, digits) = match rest.first()?.node {
+        b'\'' if rest.len() == 2 => (Base::Character, &rest[1..]),
+        b'+' if rest.len() > 1 && !negative => (Base::Decimal, &rest[1..]),
+        b'%' if rest.len() > 1 => (Base::Binary, &rest[1..]),
+        b'x' if rest.len() > 1 => (Base::Hexadecimal, &rest[1..]),
+        _ if negative => (Base::Decimal, rest),
+        _ => (Base::Hexadecimal, rest),
+    };
+
+    let is_extended = negative || size.is_some() || digits.len() < rest.len();
+    if !is_extended {
+        return None;
+    }
+
+    Some(Parts {
+        negative,
+        base,
+        digits,
+        size,
+    })
+}
+
+/// Returns whether the symbols after a literal hex rune are a number in one of the extended
+/// syntaxes: a decimal number like `#+200`, a binary number like `#%1010_0101`, an explicitly
+/// hexadecimal number like `#x1`, a character like `#'a`, a negative number like `#-3`, or any
+/// of them followed by a size suffix, like `#1:s` or `#+12:b`.
+pub(crate) fn is_number(symbols: &[Spanned<u8>]) -> bool {
+    split(symbols).is_some()
+}
+
+/// Parses a number in one of the extended syntaxes. Negative numbers are two's complement.
+///
+/// `symbols` must be a number according to [`is_number`].
+pub(crate) fn parse_number(symbols: &[Spanned<u8>]) -> Result<HexNumber, Error> {
+    let parts = split(symbols).unwrap();
+    let number = String::from_utf8_lossy(&to_string(symbols)).into_owned();
+    let span = to_span(symbols).unwrap();
+
+    let mut value: u32 = 0;
+    let mut digit_count: usize = 0;
+    for Spanned { node: ch, span } in parts.digits {
+        if !parts.base.is_digit(*ch) {
+            let digit = *ch as char;
+            let span = (*span).into();
+            let number = number.clone();
+            return Err(match parts.base {
+                Base::Binary => Error::BinaryDigitInvalid {
+                    digit,
+                    number,
+                    span,
+                },
+                Base::Decimal => Error::DecimalDigitInvalid {
+                    digit,
+                    number,
+                    span,
+                },
+                _ => Error::HexDigitInvalid {
+                    digit,
+                    number,
+                    span,
+                },
+            });
+        }
+        let digit = match (parts.base, *ch) {
+            (_, b'_') => continue,
+            (Base::Character, ch) => ch as u32,
+            (_, ch) => (ch as char).to_digit(16).unwrap(),
+        };
+        let radix = match parts.base {
+            Base::Binary => 2,
+            Base::Decimal => 10,
+            Base::Hexadecimal => 16,
+            Base::Character => 256,
+        };
+        // Anything above this is out of range anyway, so the value does not have to grow.
+        value = (value * radix + digit).min(0x10000);
+        digit_count += 1;
+    }
+    if digit_count == 0 {
+        return Err(Error::DigitExpected { span: span.into() });
+    }
+
+    let size = match parts.size {
+        Some(size) => size,
+        None => match parts.base {
+            Base::Hexadecimal if digit_count > 4 => {
+                return Err(Error::HexNumberTooLong {
+                    length: digit_count,
+                    number,
+                    span: span.into(),
+                })
+            }
+            Base::Hexadecimal if digit_count > 2 => Size::Short,
+            Base::Binary if digit_count > 8 => Size::Short,
+            Base::Decimal if value > 0xff || (parts.negative && value > 0x80) => Size::Short,
+            _ => Size::Byte,
+        },
+    };
+
+    let (minimum, maximum) = match size {
+        Size::Byte => (-0x80, 0xff),
+        Size::Short => (-0x8000, 0xffff),
+    };
+    let signed_value = match parts.negative {
+        true => -(value as i32),
+        false => value as i32,
+    };
+    if signed_value < minimum || signed_value > maximum {
+        return Err(Error::NumberOutOfRange {
+            number,
+            minimum,
+            maximum,
+            span: span.into(),
+        });
+    }
+
+    Ok(match size {
+        Size::Byte => HexNumber::Byte(signed_value as u8),
+        Size::Short => HexNumber::Short(signed_value as u16),
+    })
+}
@@ -0,0 +1,115 @@
+use super::{Span, Spanned};
+use crate::anomalies::Error;
+
+/// What gets added to a quoted string, besides its characters.
+enum Modifier {
+    None,
+    /// A null byte after the string, written as `"text"z`.
+    NullTerminator,
+    /// A byte with the length of the string before the string, written as `"text"p`.
+    LengthPrefix,
+}
+
+/// Returns the index of the closing quote of a quoted string, like `"Hello, World!\n"`, along with
+/// the modifier after it, if the word is a quoted string.
+fn find_closing_quote(word: &[Spanned<u8>]) -> Option<(usize, Modifier)> {
+    let (closing, modifier) = match word.last()?.node {
+        b'"' => (word.len() - 1, Modifier::None),
+        b'z' => (word.len().checked_sub(2)?, Modifier::NullTerminator),
+        b'p' => (word.len().checked_sub(2)?, Modifier::LengthPrefix),
+        _ => return None,
+    };
+    if closing == 0 || word[closing].node != b'"' {
+        return None;
+    }
+
+    let backslashes = word[1..closing]
+        .iter()
+        .rev()
+        .take_while(|symbol| symbol.node == b'\\')
+        .count();
+    if backslashes % 2 == 1 {
+        return None;
+    }
+
+    Some((closing, modifier))
+}
+
+/// Returns whether the word, starting with a double quote, is a quoted string rather than a raw
+/// word that runs up to the next whitespace.
+pub(crate) fn is_quoted_string(word: &[Spanned<u8>]) -> bool {
+    find_closing_quote(word).is_some()
+}
+
+/// Parses a quoted string, replacing the escape sequences with the bytes they stand for.
+///
+/// `word` must be a quoted string.
+pub(crate) fn parse_quoted_string(word: &[Spanned<u8>]) -> Result<Vec<u8>, Error> {
+    let (closing, modifier) = find_closing_quote(word).unwrap();
+
+    let body = &word[1..closing];
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut index = 0;
+    while index < body.len() {
+        if body[index].node != b'\\' {
+            bytes.push(body[index].node);
+            index += 1;
+            continue;
+        }
+
+        let length = match body.get(index + 1) {
+            Some(Spanned { node: b'x', .. }) => 4,
+            _ => 2,
+        };
+        let sequence = &body[index..body.len().min(index + length)];
+        match parse_escape_sequence(sequence) {
+            Some(byte) => bytes.push(byte),
+            None => {
+                return Err(Error::EscapeSequenceInvalid {
+                    sequence: sequence.iter().map(|symbol| symbol.node as char).collect(),
+                    span: Span::combine(&sequence[0].span, &sequence[sequence.len() - 1].span)
+                        .into(),
+                })
+            }
+        }
+        index += length;
+    }
+
+    match modifier {
+        Modifier::None => (),
+        Modifier::NullTerminator => bytes.push(0),
+        Modifier::LengthPrefix => {
+            if bytes.len() > 0xff {
+                return Err(Error::StringTooLong {
+                    length: bytes.len(),
+                    span: Span::combine(&word[0].span, &word[word.len() - 1].span).into(),
+                });
+            }
+            bytes.insert(0, bytes.len() as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn parse_escape_sequence(sequence: &[Spanned<u8>]) -> Option<u8> {
+    let chars: Vec<u8> = sequence.iter().map(|symbol| symbol.node).collect();
+    match chars.as_slice() {
+        [b'\\', b'n'] => Some(b'\n'),
+        [b'\\', b't'] => Some(b'\t'),
+        [b'\\', b'r'] => Some(b'\r'),
+        [b'\\', b'0'] => Some(0),
+        [b'\\', b'\\'] => Some(b'\\'),
+        [b'\\', b'"'] => Some(b'"'),
+        [b'\\', b'x', high, low] => Some((to_hex_digit(*high)? << 4) | to_hex_digit(*low)?),
+        _ => None,
+    }
+}
+
+fn to_hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        _ => None,
+    }
+}
@@ -1,55 +1,271 @@
+use crate::builtins::{self, Builtin, Environment};
+use crate::expression;
 use crate::span::Spanning;
+use crate::token::Directive;
 use crate::token::Identifier;
+use crate::token::Operand;
+use crate::token::Reference;
 use crate::token::ScopedIdentifier;
 use crate::token::Statement;
-use crate::{tokenizer::Word, Span, Spanned, Token};
-use crate::{Error, Warning};
+use crate::{
+    tokenizer::{find_placeholder, Word},
+    Location, Span, Spanned, Token,
+};
+use crate::{Error, FileId, Instruction, InstructionKind, Symbol, Warning};
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::iter::Peekable;
+use std::rc::Rc;
 use std::slice::Iter;
 
 pub(crate) struct Definitions {
     pub labels: HashMap<ScopedIdentifier, (u16, Span)>,
+    pub constants: HashSet<ScopedIdentifier>,
+    /// Definitions of every numeric label name, in the order of the statements, each with the
+    /// index of the statement that it is defined before.
+    pub numeric_labels: HashMap<Vec<u8>, Vec<(usize, u16, Span)>>,
+    /// Labels that have already been used by the walker, e.g. in pads.
+    pub used_labels: HashSet<ScopedIdentifier>,
 }
 
-pub(crate) struct Walker<'words> {
+impl Definitions {
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = self
+            .labels
+            .iter()
+            .filter(|(scoped_identifier, _)| !Builtin::is_builtin(scoped_identifier))
+            .map(|(scoped_identifier, (address, _))| Symbol {
+                name: scoped_identifier.to_string(),
+                address: *address,
+            })
+            .collect();
+        symbols.sort_by(|a, b| a.address.cmp(&b.address).then_with(|| a.name.cmp(&b.name)));
+        symbols
+    }
+}
+
+/// A conditional assembly block, opened by one of the conditional assembly directives.
+struct Conditional {
+    span: Span,
+    enclosing_active: bool,
+    condition: bool,
+    else_span: Option<Span>,
+}
+
+impl Conditional {
+    fn is_active(&self) -> bool {
+        self.enclosing_active && (self.condition != self.else_span.is_some())
+    }
+}
+
+/// A relative jump emitted by a structured control-flow directive, to a hidden label. The jump
+/// gets widened to an absolute one if the label turns out to be too far for a relative jump.
+#[derive(Clone, Copy)]
+struct Jump {
+    /// Index of the statement with the address of the jump.
+    position: usize,
+    address: u16,
+    section: usize,
+    /// Number of the relocations recorded before the jump.
+    relocations: usize,
+    /// Id of the hidden label.
+    target: usize,
+}
+
+/// A structured control-flow block.
+enum Block {
+    /// A block opened by `^when`, along with the jump over its current branch.
+    When {
+        span: Span,
+        jump: Jump,
+        otherwise_span: Option<Span>,
+    },
+    /// A block opened by `^while`, along with the id of the hidden label at its start, and the
+    /// jump out of the loop, emitted by `^do`.
+    While {
+        span: Span,
+        start: usize,
+        exit: Option<(Jump, Span)>,
+    },
+}
+
+/// Something placed at an address while a structured control-flow block is open, which has to
+/// move along with the code when a jump before it gets widened.
+enum Relocation {
+    Label(ScopedIdentifier),
+    /// A numeric label, along with the index of its definition.
+    NumericLabel(Vec<u8>, usize),
+    /// A pad at the position of the section switch statement.
+    SectionSwitch(usize),
+    /// An absolute pad, along with the pointer before it, the address that it pads to, and its
+    /// span. Nothing after it moves, but what is placed before it must not move past the address.
+    AbsolutePad(u16, u16, Span),
+    /// An alignment, at the position of its pad statement, along with the alignment. The pad
+    /// shrinks, or grows by the alignment, so that what follows it stays aligned.
+    Alignment(usize, u16),
+    /// A relative pad by the value of a label or an expression, at the position of its statement.
+    /// The pad shrinks, as far as it can, so that what follows it stays in place.
+    ResolvedPad(usize),
+}
+
+/// A named section of the program, with its own pointer. The default section, `code`, and the
+/// `zeropage` section have absolute addresses. Other sections are floating, with addresses
+/// relative to their start, until they get placed after the walk.
+struct Section {
+    name: Vec<u8>,
+    /// Span of the directive that first switched to the section.
+    span: Option<Span>,
+    fixed: bool,
+    /// Whether the section only reserves memory, without any bytes in the binary.
+    uninitialized: bool,
+    pointer: u16,
+    length: u16,
+    /// The largest alignment requested in the section, which its start gets aligned to.
+    alignment: u16,
+}
+
+impl Section {
+    fn new(name: Vec<u8>, span: Option<Span>) -> Self {
+        Self {
+            fixed: name == b"code" || name == b"zeropage",
+            uninitialized: name == b"zeropage" || name == b"bss",
+            name,
+            span,
+            pointer: 0,
+            length: 0,
+            alignment: 1,
+        }
+    }
+}
+
+/// Words that have to be walked before the rest of the words that the walker has stopped at.
+pub(crate) enum Expansion {
+    /// The body of a macro along with the name of the macro, or the body of a repeat block.
+    Block(Vec<Rc<Word>>, Option<Vec<u8>>),
+    /// The words of a source file, given by its path.
+    File(Vec<u8>),
+    /// The bytes of a binary file, given by its path, which can be followed by an offset and a
+    /// length.
+    BinaryFile(Vec<u8>),
+}
+
+pub(crate) struct Walker {
     statements: Vec<Spanned<Statement>>,
     errors: Vec<Error>,
     warnings: Vec<Warning>,
+    /// The pointer and the length of the current section.
     pointer: u16,
     length: u16,
+    sections: Vec<Section>,
+    section: usize,
+    /// Labels defined in floating sections, along with the index of the section.
+    label_sections: HashMap<ScopedIdentifier, usize>,
+    /// Numeric labels defined in floating sections, as the name, the index of the definition,
+    /// and the index of the section.
+    numeric_label_sections: Vec<(Vec<u8>, usize, usize)>,
+    /// Indices of the statements that switch to a floating section, along with the index of the
+    /// section.
+    section_switches: Vec<(usize, usize)>,
     opened_brackets: Vec<Span>,
     opened_braces: Vec<Span>,
-    scope: Option<Vec<u8>>,
-    macro_definitions: HashMap<Vec<u8>, (Vec<&'words Word>, Span)>,
+    opened_lambdas: Vec<Option<usize>>,
+    lambda_count: usize,
+    conditionals: Vec<Conditional>,
+    constants: HashMap<Vec<u8>, u16>,
+    /// Names of the most recently defined label and of its most recently defined nested
+    /// sublabels.
+    scope: Vec<Vec<u8>>,
+    /// Ids of the macro expansions that are currently being walked, the innermost one last. Repeat
+    /// blocks take over the id of the expansion that they are in.
+    expansions: Vec<Option<usize>>,
+    expansion_count: usize,
+    macro_definitions: HashMap<Vec<u8>, (Vec<Vec<u8>>, Vec<Rc<Word>>, Span)>,
     unused_macros: HashSet<Vec<u8>>,
     label_definitions: HashMap<ScopedIdentifier, (u16, Span)>,
+    constant_definitions: HashSet<ScopedIdentifier>,
+    numeric_label_definitions: HashMap<Vec<u8>, Vec<(usize, u16, Span)>>,
+    used_labels: HashSet<ScopedIdentifier>,
+    /// Pads that refer to a label that is not defined yet, along with the error to report if the
+    /// label does not get defined later either.
+    unresolved_pads: Vec<(Reference, Span, Error)>,
+    /// End of the zero-page memory reserved in the fixed sections so far.
+    zero_page_end: u16,
+    /// Zero-page allocations as the label, the size, and the span of the allocation. They get
+    /// their addresses once all of the zero-page memory reserved in the fixed sections is known.
+    allocations: Vec<(ScopedIdentifier, u16, Span)>,
     zeroth_page_spans: Vec<Span>,
     overflow_spans: Vec<Span>,
+    /// Modules that the labels defined in the rest of a file are put in, by the file.
+    modules: HashMap<FileId, Vec<u8>>,
+    /// Structured control-flow blocks that are open, the innermost one last.
+    blocks: Vec<Block>,
+    /// Everything placed while any structured block is open, along with the index of its section.
+    relocations: Vec<(usize, Relocation)>,
+    environment: Environment,
+    /// Indices of the raw words that stand for the length of the program, which is only known
+    /// after the walk.
+    length_texts: Vec<usize>,
 }
 
-impl<'words> Walker<'words> {
-    pub fn new() -> Self {
-        Self {
+impl Walker {
+    pub fn new(definitions: &[(Vec<u8>, u16)], environment: Environment) -> Self {
+        let mut walker = Self {
             statements: Vec::new(),
             errors: Vec::new(),
             warnings: Vec::new(),
             pointer: 0,
             length: 0,
+            sections: vec![Section::new(b"code".to_vec(), None)],
+            section: 0,
+            label_sections: HashMap::new(),
+            numeric_label_sections: Vec::new(),
+            section_switches: Vec::new(),
             opened_brackets: Vec::new(),
             opened_braces: Vec::new(),
-            scope: None,
+            opened_lambdas: Vec::new(),
+            lambda_count: 0,
+            conditionals: Vec::new(),
+            constants: definitions.iter().cloned().collect(),
+            scope: Vec::new(),
+            expansions: Vec::new(),
+            expansion_count: 0,
             macro_definitions: HashMap::new(),
             unused_macros: HashSet::new(),
             label_definitions: HashMap::new(),
+            constant_definitions: HashSet::new(),
+            numeric_label_definitions: HashMap::new(),
+            used_labels: HashSet::new(),
+            unresolved_pads: Vec::new(),
+            zero_page_end: 0,
+            allocations: Vec::new(),
             zeroth_page_spans: Vec::new(),
             overflow_spans: Vec::new(),
-        }
+            modules: HashMap::new(),
+            blocks: Vec::new(),
+            relocations: Vec::new(),
+            environment,
+            length_texts: Vec::new(),
+        };
+        walker.define_builtin(Builtin::Version, builtins::version_number());
+        let days = walker.environment.timestamp / 86_400;
+        walker.define_builtin(Builtin::Date, u16::try_from(days).unwrap_or(u16::MAX));
+        walker
+    }
+
+    /// Updates the names of the files taking part in the assembly, once a file has been included.
+    pub fn set_file_names(&mut self, file_names: Vec<String>) {
+        self.environment.file_names = file_names;
     }
 
     pub fn push_bytes(&mut self, bytes: u16, span: Span) {
-        if self.pointer < 256 {
+        let section = &self.sections[self.section];
+        if section.uninitialized {
+            self.errors.push(Error::BytesInUninitializedSection {
+                section: String::from_utf8_lossy(&section.name).into_owned(),
+                span: span.into(),
+            });
+        } else if section.fixed && self.pointer < 256 {
             self.zeroth_page_spans.push(span);
         }
         self.increment_pointer(bytes, span);
@@ -67,24 +283,61 @@ impl<'words> Walker<'words> {
     pub fn increment_pointer(&mut self, by: u16, span: Span) {
         match self.pointer.checked_add(by) {
             Some(result) => {
+                if self.sections[self.section].fixed && self.pointer < 0x100 {
+                    self.zero_page_end = self.zero_page_end.max(result.min(0x100));
+                }
                 self.pointer = result;
             }
             None => self.overflow_spans.push(span),
         }
     }
 
-    pub fn walk(
-        &mut self,
-        words: &[&'words Word],
-    ) -> Option<(Vec<&'words Word>, Vec<u8>, Span, Vec<&'words Word>)> {
+    pub fn walk(&mut self, words: &[Rc<Word>]) -> Option<(Expansion, Span, Vec<Rc<Word>>)> {
         let mut words = words.iter().peekable();
 
         loop {
-            match words.next() {
+            match words.next().map(Rc::as_ref) {
                 Some(Word::Fine {
                     token,
                     warnings: new_warnings,
                 }) => {
+                    if self.is_skipping() {
+                        match token {
+                            Spanned {
+                                node: Token::OpeningBracket,
+                                ..
+                            }
+                            | Spanned {
+                                node: Token::ClosingBracket,
+                                ..
+                            }
+                            | Spanned {
+                                node: Token::ClosingBrace,
+                                ..
+                            }
+                            | Spanned {
+                                node: Token::Directive(_),
+                                ..
+                            } => (),
+                            Spanned {
+                                node: Token::OpeningBrace,
+                                span,
+                            }
+                            | Spanned {
+                                node: Token::OpeningJumpBrace,
+                                span,
+                            }
+                            | Spanned {
+                                node: Token::OpeningConditionalJumpBrace,
+                                span,
+                            } => {
+                                self.opened_braces.push(*span);
+                                self.opened_lambdas.push(None);
+                                continue;
+                            }
+                            _ => continue,
+                        }
+                    }
                     self.warnings.extend(new_warnings.iter().cloned());
                     match token {
                         Spanned {
@@ -107,22 +360,34 @@ impl<'words> Walker<'words> {
                             node: Token::OpeningBrace,
                             span,
                         } => {
-                            self.opened_braces.push(*span);
-                            self.errors
-                                .push(Error::OpeningBraceNotAfterMacroDefinition {
-                                    span: (*span).into(),
-                                });
+                            self.open_lambda(Statement::ImmediateCall, *span);
                         }
                         Spanned {
-                            node: Token::ClosingBrace,
+                            node: Token::OpeningJumpBrace,
                             span,
                         } => {
-                            if self.opened_braces.pop().is_none() {
-                                self.errors.push(Error::NoMatchingOpeningBrace {
-                                    span: (*span).into(),
-                                });
-                            }
+                            self.open_lambda(Statement::ImmediateJump, *span);
+                        }
+                        Spanned {
+                            node: Token::OpeningConditionalJumpBrace,
+                            span,
+                        } => {
+                            self.open_lambda(Statement::ImmediateConditionalJump, *span);
                         }
+                        Spanned {
+                            node: Token::ClosingBrace,
+                            span,
+                        } => match self.opened_lambdas.pop() {
+                            Some(lambda) => {
+                                self.opened_braces.pop();
+                                if let Some(id) = lambda {
+                                    self.define_label(ScopedIdentifier::Lambda(id), *span);
+                                }
+                            }
+                            None => self.errors.push(Error::NoMatchingOpeningBrace {
+                                span: (*span).into(),
+                            }),
+                        },
                         Spanned {
                             node: Token::Instruction(instruction),
                             span,
@@ -132,139 +397,320 @@ impl<'words> Walker<'words> {
                             self.push_bytes(1, *span);
                         }
                         Spanned {
-                            node: Token::MacroDefine(name),
+                            node: Token::MacroDefine(name, parameters),
                             span,
                         } => {
-                            words = self.walk_macro_definition(name, *span, words);
+                            words = self.walk_macro_definition(name, parameters, *span, words);
                         }
                         Spanned {
-                            node: Token::MacroInvoke(name),
+                            node: Token::MacroInvoke(name, arguments),
                             span,
                         } => match self.macro_definitions.get(name) {
-                            Some((items, _)) => {
+                            Some((parameters, items, other_span)) => {
                                 self.unused_macros.remove(name);
+                                if arguments.len() != parameters.len() {
+                                    self.errors.push(Error::MacroArgumentCountMismatch {
+                                        name: String::from_utf8_lossy(&name).into_owned(),
+                                        expected: parameters.len(),
+                                        found: arguments.len(),
+                                        span: (*span).into(),
+                                        other_span: (*other_span).into(),
+                                    });
+                                    continue;
+                                }
+                                let items = if parameters.is_empty() {
+                                    items.clone()
+                                } else {
+                                    items
+                                        .iter()
+                                        .map(|item| Rc::new(item.substitute(parameters, arguments)))
+                                        .collect()
+                                };
+                                self.expansions.push(Some(self.expansion_count));
+                                self.expansion_count += 1;
                                 return Some((
-                                    items.clone(),
-                                    name.clone(),
+                                    Expansion::Block(items, Some(name.clone())),
                                     *span,
-                                    words.copied().collect(),
+                                    words.cloned().collect(),
                                 ));
                             }
-                            None => self.errors.push(Error::MacroUndefined {
-                                name: String::from_utf8_lossy(&name).into_owned(),
-                                span: (*span).into(),
-                            }),
+                            None if !arguments.is_empty() => {
+                                self.errors.push(Error::MacroUndefined {
+                                    name: String::from_utf8_lossy(&name).into_owned(),
+                                    span: (*span).into(),
+                                })
+                            }
+                            None => {
+                                // A bare word that does not name a previously defined macro is
+                                // a subroutine call to a label.
+                                match scope_identifier(
+                                    &bare_word_identifier(name),
+                                    &self.scope,
+                                    self.expansion(),
+                                    span,
+                                ) {
+                                    Ok(scoped_identifier) => {
+                                        let reference = Reference {
+                                            namespace: self.namespace(span),
+                                            ..scoped_identifier.into()
+                                        };
+                                        self.statements.push(
+                                            Statement::ImmediateCall(reference).spanning(*span),
+                                        );
+                                        self.push_bytes(4, *span);
+                                    }
+                                    Err(err) => self.errors.push(err),
+                                }
+                            }
                         },
                         Spanned {
                             node: Token::PadAbsolute(value),
                             span,
                         } => {
-                            self.statements
-                                .push(Statement::PadAbsolute(*value).spanning(*span));
-                            match self.set_pointer(*value as u16) {
-                                Ok(()) => (),
-                                Err(previous_address) => self.errors.push(Error::PaddedBackwards {
-                                    previous_pointer: previous_address as usize,
-                                    desired_pointer: *value as usize,
-                                    span: (*span).into(),
-                                }),
-                            }
+                            self.pad_absolute(*value, *span);
                         }
                         Spanned {
                             node: Token::PadRelative(value),
                             span,
                         } => {
-                            self.statements
-                                .push(Statement::PadRelative(*value).spanning(*span));
-                            self.increment_pointer(*value as u16, *span);
+                            self.pad_relative(*value, *span);
+                        }
+                        Spanned {
+                            node: Token::PadAbsoluteExpression(operand, fallback),
+                            span,
+                        } => {
+                            if let Some(value) = self.resolve_pad(operand, fallback, span) {
+                                self.pad_absolute(value, *span);
+                            }
+                        }
+                        Spanned {
+                            node: Token::PadRelativeExpression(operand, fallback),
+                            span,
+                        } => {
+                            if let Some(value) = self.resolve_pad(operand, fallback, span) {
+                                self.record(Relocation::ResolvedPad(self.statements.len()));
+                                self.pad_relative(value, *span);
+                            }
                         }
                         Spanned {
                             node: Token::LabelDefine(name),
                             span,
                         } => {
-                            if let Some((_, other_span)) = self.label_definitions.insert(
-                                ScopedIdentifier::Label(name.clone()),
-                                (self.pointer, *span),
-                            ) {
+                            let scoped_identifier =
+                                self.qualify(ScopedIdentifier::Label(name.clone()), span);
+                            if let Some(other_span) =
+                                self.define_label(scoped_identifier.clone(), *span)
+                            {
                                 self.errors.push(Error::LabelDefinedMoreThanOnce {
-                                    name: String::from_utf8_lossy(&name).into_owned(),
+                                    name: scoped_identifier.to_string(),
                                     span: (*span).into(),
                                     other_span: other_span.into(),
                                 });
                             }
-                            self.scope = Some(name.clone());
+                            if let ScopedIdentifier::Label(name) = scoped_identifier {
+                                self.scope = vec![name];
+                            }
                         }
                         Spanned {
-                            node: Token::SublabelDefine(name),
+                            node: Token::SublabelDefine(depth, name),
                             span,
-                        } => match &self.scope {
-                            Some(scope_name) => {
-                                if let Some((_, other_span)) = self.label_definitions.insert(
-                                    ScopedIdentifier::Sublabel(scope_name.to_owned(), name.clone()),
-                                    (self.pointer, *span),
-                                ) {
+                        } => match self.sublabel_identifier(*depth, name, span) {
+                            Ok(scoped_identifier) => {
+                                if let Some(other_span) =
+                                    self.define_label(scoped_identifier.clone(), *span)
+                                {
                                     self.errors.push(Error::LabelDefinedMoreThanOnce {
-                                        name: String::from_utf8_lossy(&name).into_owned(),
+                                        name: scoped_identifier.to_string(),
                                         span: (*span).into(),
                                         other_span: other_span.into(),
                                     });
                                 }
+                                self.scope.truncate(*depth);
+                                self.scope.push(name.clone());
                             }
-                            None => self.errors.push(Error::SublabelDefinedWithoutScope {
-                                name: String::from_utf8_lossy(&name).into_owned(),
+                            Err(err) => self.errors.push(err),
+                        },
+                        Spanned {
+                            node: Token::LocalLabelDefine(name),
+                            span,
+                        } => match self.expansion() {
+                            Some(expansion) => {
+                                let scoped_identifier =
+                                    ScopedIdentifier::Local(name.clone(), expansion);
+                                if let Some(other_span) =
+                                    self.define_label(scoped_identifier, *span)
+                                {
+                                    self.errors.push(Error::LabelDefinedMoreThanOnce {
+                                        name: format!("%{}", String::from_utf8_lossy(name)),
+                                        span: (*span).into(),
+                                        other_span: other_span.into(),
+                                    });
+                                }
+                            }
+                            None => self.errors.push(Error::LocalLabelDefinedOutsideMacro {
+                                name: String::from_utf8_lossy(name).into_owned(),
                                 span: (*span).into(),
                             }),
                         },
                         Spanned {
-                            node: Token::LiteralZeroPageAddress(identifier),
+                            node: Token::NumericLabelDefine(name),
                             span,
-                        } => match scope_identifier(identifier, &self.scope, span) {
-                            Ok(scoped_identifier) => {
+                        } => {
+                            let definitions = self
+                                .numeric_label_definitions
+                                .entry(name.clone())
+                                .or_default();
+                            if !self.sections[self.section].fixed {
+                                self.numeric_label_sections.push((
+                                    name.clone(),
+                                    definitions.len(),
+                                    self.section,
+                                ));
+                            }
+                            definitions.push((self.statements.len(), self.pointer, *span));
+                            let index = definitions.len() - 1;
+                            self.record(Relocation::NumericLabel(name.clone(), index));
+                        }
+                        Spanned {
+                            node: Token::LiteralZeroPageAddress(operand),
+                            span,
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
                                 self.statements.push(
-                                    Statement::LiteralZeroPageAddress(scoped_identifier)
-                                        .spanning(*span),
+                                    Statement::LiteralZeroPageAddress(reference).spanning(*span),
                                 );
                                 self.push_bytes(2, *span);
                             }
                             Err(err) => self.errors.push(err),
                         },
                         Spanned {
-                            node: Token::LiteralRelativeAddress(identifier),
+                            node: Token::LiteralRelativeAddress(operand),
                             span,
-                        } => match scope_identifier(identifier, &self.scope, span) {
-                            Ok(scoped_identifier) => {
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
                                 self.statements.push(
-                                    Statement::LiteralRelativeAddress(scoped_identifier)
-                                        .spanning(*span),
+                                    Statement::LiteralRelativeAddress(reference).spanning(*span),
                                 );
                                 self.push_bytes(2, *span);
                             }
                             Err(err) => self.errors.push(err),
                         },
                         Spanned {
-                            node: Token::LiteralAbsoluteAddress(identifier),
+                            node: Token::LiteralAbsoluteAddress(operand),
                             span,
-                        } => match scope_identifier(identifier, &self.scope, &span) {
-                            Ok(scoped_identifier) => {
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
                                 self.statements.push(
-                                    Statement::LiteralAbsoluteAddress(scoped_identifier)
-                                        .spanning(*span),
+                                    Statement::LiteralAbsoluteAddress(reference).spanning(*span),
                                 );
                                 self.push_bytes(3, *span);
                             }
                             Err(err) => self.errors.push(err),
                         },
                         Spanned {
-                            node: Token::RawAddress(identifier),
+                            node: Token::RawZeroPageAddress(operand),
                             span,
-                        } => match scope_identifier(identifier, &self.scope, span) {
-                            Ok(scoped_identifier) => {
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
+                                self.statements
+                                    .push(Statement::RawZeroPageAddress(reference).spanning(*span));
+                                self.push_bytes(1, *span);
+                            }
+                            Err(err) => self.errors.push(err),
+                        },
+                        Spanned {
+                            node: Token::RawRelativeAddress(operand),
+                            span,
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
+                                self.statements
+                                    .push(Statement::RawRelativeAddress(reference).spanning(*span));
+                                self.push_bytes(1, *span);
+                            }
+                            Err(err) => self.errors.push(err),
+                        },
+                        Spanned {
+                            node: Token::RawAddress(operand),
+                            span,
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
                                 self.statements
-                                    .push(Statement::RawAddress(scoped_identifier).spanning(*span));
+                                    .push(Statement::RawAddress(reference).spanning(*span));
                                 self.push_bytes(2, *span);
                             }
                             Err(err) => self.errors.push(err),
                         },
+                        Spanned {
+                            node: Token::ImmediateJump(operand),
+                            span,
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
+                                self.statements
+                                    .push(Statement::ImmediateJump(reference).spanning(*span));
+                                self.push_bytes(4, *span);
+                            }
+                            Err(err) => self.errors.push(err),
+                        },
+                        Spanned {
+                            node: Token::ImmediateConditionalJump(operand),
+                            span,
+                        } => match scope_operand(
+                            operand,
+                            &self.scope,
+                            self.expansion(),
+                            self.namespace(span),
+                            span,
+                        ) {
+                            Ok(reference) => {
+                                self.statements.push(
+                                    Statement::ImmediateConditionalJump(reference).spanning(*span),
+                                );
+                                self.push_bytes(4, *span);
+                            }
+                            Err(err) => self.errors.push(err),
+                        },
                         Spanned {
                             node: Token::LiteralHexByte(value),
                             span,
@@ -281,6 +727,36 @@ impl<'words> Walker<'words> {
                                 .push(Statement::LiteralHexShort(*value).spanning(*span));
                             self.push_bytes(3, *span);
                         }
+                        Spanned {
+                            node: Token::LiteralByteExpression(operand),
+                            span,
+                        } => {
+                            self.statements.push(
+                                Statement::LiteralByteExpression(scope_expression(
+                                    operand,
+                                    &self.scope,
+                                    self.expansion(),
+                                    self.namespace(span),
+                                ))
+                                .spanning(*span),
+                            );
+                            self.push_bytes(2, *span);
+                        }
+                        Spanned {
+                            node: Token::LiteralShortExpression(operand),
+                            span,
+                        } => {
+                            self.statements.push(
+                                Statement::LiteralShortExpression(scope_expression(
+                                    operand,
+                                    &self.scope,
+                                    self.expansion(),
+                                    self.namespace(span),
+                                ))
+                                .spanning(*span),
+                            );
+                            self.push_bytes(3, *span);
+                        }
                         Spanned {
                             node: Token::RawHexByte(value),
                             span,
@@ -309,12 +785,92 @@ impl<'words> Walker<'words> {
                             node: Token::RawWord(word),
                             span,
                         } => {
+                            let word = match Builtin::from_name(word) {
+                                Some(builtin) => self.builtin_text(builtin, span),
+                                None => word.clone(),
+                            };
                             self.push_bytes(word.len() as u16, *span);
                             self.statements
-                                .push(Statement::RawWord(word.clone()).spanning(*span));
+                                .push(Statement::RawWord(word).spanning(*span));
+                        }
+                        Spanned {
+                            node: Token::IncludedBinary(bytes),
+                            span,
+                        } => {
+                            match u16::try_from(bytes.len()) {
+                                Ok(length) => self.push_bytes(length, *span),
+                                Err(_) => self.overflow_spans.push(*span),
+                            }
+                            self.statements
+                                .push(Statement::IncludedBinary(bytes.clone()).spanning(*span));
+                        }
+                        Spanned {
+                            node: Token::Template(symbols),
+                            ..
+                        } => {
+                            let (start, end) = find_placeholder(symbols).unwrap();
+                            let name: Vec<u8> = symbols[start + 1..end]
+                                .iter()
+                                .map(|Spanned { node: ch, .. }| *ch)
+                                .collect();
+                            self.errors.push(Error::MacroParameterUndefined {
+                                name: String::from_utf8_lossy(&name).into_owned(),
+                                span: Span::combine(&symbols[start].span, &symbols[end].span)
+                                    .into(),
+                            });
+                        }
+                        Spanned {
+                            node: Token::Assertion(symbols),
+                            span,
+                        } => {
+                            let message = words.next().map(Rc::as_ref);
+                            self.walk_assertion(symbols, *span, message);
+                        }
+                        Spanned {
+                            node: Token::Directive(Directive::Repeat),
+                            span,
+                        } => {
+                            if let Some(items) = self.walk_repeat(*span, &mut words) {
+                                self.expansions.push(self.expansion());
+                                return Some((
+                                    Expansion::Block(items, None),
+                                    *span,
+                                    words.cloned().collect(),
+                                ));
+                            }
+                        }
+                        Spanned {
+                            node: Token::Directive(directive),
+                            span,
+                        } => {
+                            self.walk_directive(*directive, *span, &mut words);
+                        }
+                        Spanned {
+                            node: Token::Include(path),
+                            span,
+                        } => {
+                            // The included file is walked like a repeat block, in the expansion
+                            // that it is included in.
+                            self.expansions.push(self.expansion());
+                            return Some((
+                                Expansion::File(path.clone()),
+                                *span,
+                                words.cloned().collect(),
+                            ));
+                        }
+                        Spanned {
+                            node: Token::IncludeBinary(path),
+                            span,
+                        } => {
+                            return Some((
+                                Expansion::BinaryFile(path.clone()),
+                                *span,
+                                words.cloned().collect(),
+                            ));
                         }
                     }
                 }
+                Some(Word::Faulty { .. }) if self.is_skipping() => (),
                 Some(Word::Faulty {
                     errors: new_errors,
                     warnings: new_warnings,
@@ -326,6 +882,7 @@ impl<'words> Walker<'words> {
             }
         }
 
+        self.expansions.pop();
         return None;
     }
 
@@ -333,6 +890,29 @@ impl<'words> Walker<'words> {
         mut self,
     ) -> Result<(Vec<Spanned<Statement>>, Definitions, Vec<Warning>), (Vec<Error>, Vec<Warning>)>
     {
+        self.place_allocations();
+        let length = self.place_sections();
+        self.define_builtin(Builtin::Length, length);
+        for position in std::mem::take(&mut self.length_texts) {
+            self.statements[position].node =
+                Statement::RawWord(format!("{:04x}", length).into_bytes());
+        }
+        self.warn_about_colliding_labels();
+
+        for (reference, span, err) in std::mem::take(&mut self.unresolved_pads) {
+            match self.evaluate_reference(&reference, &span) {
+                Ok(_) => self.errors.push(Error::PadForwardReference {
+                    operand: reference
+                        .expression
+                        .iter()
+                        .map(|symbol| symbol.node as char)
+                        .collect(),
+                    span: span.into(),
+                }),
+                Err(_) => self.errors.push(err),
+            }
+        }
+
         for opened_bracket in self.opened_brackets {
             self.errors.push(Error::NoMatchingClosingBracket {
                 span: opened_bracket.into(),
@@ -365,8 +945,25 @@ impl<'words> Walker<'words> {
             });
         }
 
+        for block in self.blocks {
+            let (directive, span) = match block {
+                Block::When { span, .. } => ("^when", span),
+                Block::While { span, .. } => ("^while", span),
+            };
+            self.errors.push(Error::NoMatchingBlockEnd {
+                directive: directive.to_owned(),
+                span: span.into(),
+            });
+        }
+
+        for conditional in self.conditionals {
+            self.errors.push(Error::NoMatchingEndif {
+                span: conditional.span.into(),
+            })
+        }
+
         for unused_macro_name in self.unused_macros {
-            let (_, span) = self.macro_definitions[&unused_macro_name];
+            let (_, _, span) = self.macro_definitions[&unused_macro_name];
             self.warnings.push(Warning::MacroUnused {
                 name: String::from_utf8_lossy(&unused_macro_name).into_owned(),
                 span: span.into(),
@@ -378,6 +975,9 @@ impl<'words> Walker<'words> {
                 self.statements,
                 Definitions {
                     labels: self.label_definitions,
+                    constants: self.constant_definitions,
+                    numeric_labels: self.numeric_label_definitions,
+                    used_labels: self.used_labels,
                 },
                 self.warnings,
             ))
@@ -386,69 +986,1254 @@ impl<'words> Walker<'words> {
         }
     }
 
-    fn walk_macro_definition<'a>(
+    /// Reports labels that have the same first 64 characters, which uxnasm would cut their names
+    /// off at.
+    fn warn_about_colliding_labels(&mut self) {
+        let mut labels: Vec<(String, Span)> = self
+            .label_definitions
+            .iter()
+            .filter(|(scoped_identifier, _)| {
+                !matches!(scoped_identifier, ScopedIdentifier::Lambda(_))
+            })
+            .map(|(scoped_identifier, (_, span))| (scoped_identifier.to_string(), *span))
+            .collect();
+        labels.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut trimmed_names: HashMap<&[u8], (&String, Span)> = HashMap::new();
+        for (name, span) in &labels {
+            let trimmed_name = &name.as_bytes()[..name.len().min(64)];
+            match trimmed_names.get(trimmed_name) {
+                Some((other_name, other_span)) => {
+                    self.warnings.push(Warning::LabelCollidesWhenTrimmed {
+                        name: name.clone(),
+                        other_name: (*other_name).clone(),
+                        span: (*span).into(),
+                        other_span: (*other_span).into(),
+                    })
+                }
+                None => {
+                    trimmed_names.insert(trimmed_name, (name, *span));
+                }
+            }
+        }
+    }
+
+    fn is_skipping(&self) -> bool {
+        match self.conditionals.last() {
+            Some(conditional) => !conditional.is_active(),
+            None => false,
+        }
+    }
+
+    fn walk_directive(
         &mut self,
-        name: &Vec<u8>,
+        directive: Directive,
         span: Span,
-        mut words: Peekable<Iter<'a, &'words Word>>,
-    ) -> Peekable<Iter<'a, &'words Word>> {
-        let mut items: Vec<&'words Word> = Vec::new();
-
-        match words.peek() {
-            Some(Word::Fine {
-                token:
-                    Spanned {
-                        node: Token::OpeningBrace,
-                        span: opening_brace_span,
-                    },
-                warnings: new_warnings,
-            }) => {
-                let brace_level = self.opened_braces.len();
-                self.opened_braces.push(*opening_brace_span);
-                self.warnings.extend(new_warnings.iter().cloned());
-                words.next();
-                'macro_define: loop {
-                    let option_word = words.next();
-                    match &option_word {
-                        Some(Word::Fine {
-                            token:
-                                Spanned {
-                                    node: Token::OpeningBrace,
-                                    span,
-                                },
-                            warnings: new_warnings,
-                        }) => {
-                            self.opened_braces.push(*span);
-                            self.warnings.extend(new_warnings.iter().cloned());
-                            items.push(option_word.unwrap().clone());
+        words: &mut Peekable<Iter<Rc<Word>>>,
+    ) {
+        match directive {
+            Directive::If | Directive::IfDefined | Directive::IfNotDefined => {
+                let enclosing_active = !self.is_skipping();
+                let argument = words.next().map(Rc::as_ref);
+                let condition = if enclosing_active {
+                    match self.evaluate_condition(directive, argument) {
+                        Ok(condition) => condition,
+                        Err(()) => {
+                            self.errors
+                                .push(Error::ConditionExpected { span: span.into() });
+                            false
                         }
-                        Some(Word::Fine {
-                            token:
-                                Spanned {
-                                    node: Token::ClosingBrace,
-                                    ..
-                                },
-                            warnings: new_warnings,
+                    }
+                } else {
+                    false
+                };
+                self.conditionals.push(Conditional {
+                    span,
+                    enclosing_active,
+                    condition,
+                    else_span: None,
+                });
+            }
+            Directive::Else => match self.conditionals.last_mut() {
+                Some(Conditional {
+                    else_span: Some(other_span),
+                    ..
+                }) => self.errors.push(Error::ElseDefinedMoreThanOnce {
+                    span: span.into(),
+                    other_span: (*other_span).into(),
+                }),
+                Some(conditional) => conditional.else_span = Some(span),
+                None => self.errors.push(Error::NoMatchingIf { span: span.into() }),
+            },
+            Directive::EndIf => {
+                if self.conditionals.pop().is_none() {
+                    self.errors.push(Error::NoMatchingIf { span: span.into() });
+                }
+            }
+            Directive::Constant => {
+                let name = words.next().map(Rc::as_ref);
+                let value = words.next().map(Rc::as_ref);
+                if !self.is_skipping() {
+                    self.walk_constant_definition(span, name, value);
+                }
+            }
+            Directive::IncludeBinary | Directive::Assert => {
+                // Binary includes are joined with their paths, and assertions with their
+                // conditions, by the loader, before the walk.
+            }
+            Directive::Repeat => {
+                // Repeat blocks are expanded in `walk`, the same way as macros.
+            }
+            Directive::Module => {
+                let name = words.next().map(Rc::as_ref);
+                if !self.is_skipping() {
+                    self.walk_module(span, name);
+                }
+            }
+            Directive::When
+            | Directive::Otherwise
+            | Directive::End
+            | Directive::While
+            | Directive::Do
+            | Directive::Loop => {
+                if !self.is_skipping() {
+                    self.walk_block_directive(directive, span);
+                }
+            }
+            Directive::Error | Directive::Warning => {
+                let message = words.next().map(Rc::as_ref);
+                if !self.is_skipping() {
+                    self.walk_diagnostic(directive, span, message);
+                }
+            }
+            Directive::Align => {
+                let alignment = words.next().map(Rc::as_ref);
+                if !self.is_skipping() {
+                    self.walk_alignment(span, alignment);
+                }
+            }
+            Directive::Allocate => {
+                let name = words.next().map(Rc::as_ref);
+                let size = words.next().map(Rc::as_ref);
+                if !self.is_skipping() {
+                    self.walk_allocation(span, name, size);
+                }
+            }
+            Directive::Section => {
+                let name = words.next().map(Rc::as_ref);
+                if !self.is_skipping() {
+                    self.walk_section(span, name);
+                }
+            }
+            Directive::Fill => {
+                let length = words.next().map(Rc::as_ref);
+                let value = words.next().map(Rc::as_ref);
+                if !self.is_skipping() {
+                    self.walk_fill(span, length, value);
+                }
+            }
+        }
+    }
+
+    fn pad_absolute(&mut self, value: u16, span: Span) {
+        let section = &self.sections[self.section];
+        if !section.fixed {
+            self.errors.push(Error::AbsolutePadInFloatingSection {
+                section: String::from_utf8_lossy(&section.name).into_owned(),
+                span: span.into(),
+            });
+            return;
+        }
+        self.statements
+            .push(Statement::PadAbsolute(value).spanning(span));
+        self.record(Relocation::AbsolutePad(self.pointer, value, span));
+        match self.set_pointer(value) {
+            Ok(()) => (),
+            Err(previous_address) => self.errors.push(Error::PaddedBackwards {
+                previous_pointer: previous_address as usize,
+                desired_pointer: value as usize,
+                span: span.into(),
+            }),
+        }
+    }
+
+    fn pad_relative(&mut self, value: u16, span: Span) {
+        self.statements
+            .push(Statement::PadRelative(value).spanning(span));
+        self.increment_pointer(value, span);
+    }
+
+    /// Resolves the operand of a pad with the labels defined so far. A pad can not refer to a
+    /// label defined after it, as the address of the label could depend on the pad. The
+    /// `fallback` error, if there is one, is reported instead of the label being undefined.
+    fn resolve_pad(
+        &mut self,
+        operand: &Operand,
+        fallback: &Option<Error>,
+        span: &Span,
+    ) -> Option<u16> {
+        let reference = match scope_operand(
+            operand,
+            &self.scope,
+            self.expansion(),
+            self.namespace(span),
+            span,
+        ) {
+            Ok(reference) => reference,
+            Err(err) => {
+                self.errors.push(err);
+                return None;
+            }
+        };
+        match self.evaluate_reference(&reference, span) {
+            Ok(value) => Some(value),
+            Err(err @ Error::LabelUndefined { .. }) => {
+                let err = fallback.clone().unwrap_or(err);
+                self.unresolved_pads.push((reference, *span, err));
+                None
+            }
+            Err(err) => {
+                self.errors.push(err);
+                None
+            }
+        }
+    }
+
+    /// Evaluates a reference to a label, or an expression, with the labels defined so far.
+    fn evaluate_reference(&mut self, reference: &Reference, span: &Span) -> Result<u16, Error> {
+        if let Some(identifier) = &reference.identifier {
+            if self.is_placed_later(identifier) {
+                return Err(Error::LabelUndefined {
+                    name: identifier.to_string(),
+                    span: (*span).into(),
+                });
+            }
+            if let Some((value, _)) = self.label_definitions.get(identifier) {
+                self.used_labels.insert(identifier.clone());
+                return Ok(*value);
+            }
+            return Err(Error::LabelUndefined {
+                name: identifier.to_string(),
+                span: (*span).into(),
+            });
+        }
+
+        let evaluation = expression::evaluate(
+            &reference.expression,
+            &reference.scope,
+            reference.expansion,
+            reference.namespace.as_deref(),
+            &self.label_definitions,
+        )?;
+        if let Some(identifier) = evaluation
+            .identifiers
+            .iter()
+            .find(|identifier| self.is_placed_later(identifier))
+        {
+            return Err(Error::LabelUndefined {
+                name: identifier.to_string(),
+                span: (*span).into(),
+            });
+        }
+        self.used_labels.extend(evaluation.identifiers);
+        if !(0..=0xffff).contains(&evaluation.value) {
+            return Err(Error::ExpressionOverflow {
+                value: evaluation.value,
+                maximum: 0xffff,
+                span: (*span).into(),
+            });
+        }
+        Ok(evaluation.value as u16)
+    }
+
+    /// Defines a label at the current address. Returns the span of the previous definition of the
+    /// label, if there is one.
+    fn define_label(&mut self, scoped_identifier: ScopedIdentifier, span: Span) -> Option<Span> {
+        if !self.sections[self.section].fixed {
+            self.label_sections
+                .insert(scoped_identifier.clone(), self.section);
+        }
+        self.record(Relocation::Label(scoped_identifier.clone()));
+        self.label_definitions
+            .insert(scoped_identifier, (self.pointer, span))
+            .map(|(_, other_span)| other_span)
+    }
+
+    /// Switches to the section with the given name, creating it if it does not exist yet. The
+    /// section continues where it has been left off.
+    fn walk_section(&mut self, span: Span, name: Option<&Word>) {
+        let name = match name {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::MacroInvoke(name, arguments),
+                        ..
+                    },
+                ..
+            }) if arguments.is_empty() => name,
+            _ => {
+                self.errors
+                    .push(Error::SectionNameExpected { span: span.into() });
+                return;
+            }
+        };
+        let index = self.section_index(name, span);
+        let section = &mut self.sections[self.section];
+        section.pointer = self.pointer;
+        section.length = self.length;
+        self.section = index;
+        self.pointer = self.sections[index].pointer;
+        self.length = self.sections[index].length;
+
+        if !self.sections[index].fixed {
+            self.section_switches.push((self.statements.len(), index));
+        }
+        self.record(Relocation::SectionSwitch(self.statements.len()));
+        self.statements
+            .push(Statement::PadAbsolute(self.pointer).spanning(span));
+    }
+
+    /// Returns the index of the section with the given name, creating the section if it does not
+    /// exist yet.
+    fn section_index(&mut self, name: &[u8], span: Span) -> usize {
+        match self
+            .sections
+            .iter()
+            .position(|section| section.name == name)
+        {
+            Some(index) => index,
+            None => {
+                self.sections.push(Section::new(name.to_vec(), Some(span)));
+                self.sections.len() - 1
+            }
+        }
+    }
+
+    /// Returns whether the address of a label is not known until the end of the walk, i.e. whether
+    /// the label is defined in a floating section or is a zero-page allocation.
+    fn is_placed_later(&self, scoped_identifier: &ScopedIdentifier) -> bool {
+        self.label_sections.contains_key(scoped_identifier)
+            || self
+                .allocations
+                .iter()
+                .any(|(allocation, _, _)| allocation == scoped_identifier)
+    }
+
+    /// Gives the zero-page allocations their addresses, in order, past all of the zero-page
+    /// memory reserved in the fixed sections.
+    fn place_allocations(&mut self) {
+        let mut address = self.zero_page_end as u32;
+        for (scoped_identifier, size, span) in std::mem::take(&mut self.allocations) {
+            let end = address + size as u32;
+            if end > 0x100 {
+                self.errors.push(Error::ZeroPageExhausted {
+                    name: scoped_identifier.to_string(),
+                    size,
+                    span: span.into(),
+                });
+                continue;
+            }
+            if let Some((value, _)) = self.label_definitions.get_mut(&scoped_identifier) {
+                *value = address as u16;
+            }
+            address = end;
+        }
+    }
+
+    /// Places the floating sections after the fixed ones, with the sections that only reserve
+    /// memory last, and moves the labels defined in them to their final addresses.
+    ///
+    /// Returns the length of the program, i.e. of the initialized sections past the zeroth page.
+    fn place_sections(&mut self) -> u16 {
+        let section = &mut self.sections[self.section];
+        section.pointer = self.pointer;
+        section.length = self.length;
+
+        let mut next: u32 = self
+            .sections
+            .iter()
+            .filter(|section| section.fixed && !section.uninitialized)
+            .map(|section| section.pointer.max(section.length) as u32)
+            .fold(0x0100, u32::max);
+        let mut bases: Vec<u16> = vec![0; self.sections.len()];
+        let sections = &self.sections;
+        let floating = |uninitialized: bool| {
+            (0..sections.len()).filter(move |index| {
+                !sections[*index].fixed && sections[*index].uninitialized == uninitialized
+            })
+        };
+        let order: Vec<usize> = floating(false).chain(floating(true)).collect();
+        for index in order {
+            let section = &self.sections[index];
+            let alignment = section.alignment as u32;
+            let base = (next + alignment - 1) / alignment * alignment;
+            next = base + section.pointer as u32;
+            if next > 0xffff {
+                self.overflow_spans.push(section.span.unwrap());
+                continue;
+            }
+            bases[index] = base as u16;
+        }
+
+        for (scoped_identifier, index) in self.label_sections.drain() {
+            if let Some((address, _)) = self.label_definitions.get_mut(&scoped_identifier) {
+                *address += bases[index];
+            }
+        }
+        for (name, position, index) in self.numeric_label_sections.drain(..) {
+            if let Some(definitions) = self.numeric_label_definitions.get_mut(&name) {
+                definitions[position].1 += bases[index];
+            }
+        }
+        for (position, index) in self.section_switches.drain(..) {
+            if let Statement::PadAbsolute(address) = &mut self.statements[position].node {
+                *address += bases[index];
+            }
+        }
+
+        let end = self
+            .sections
+            .iter()
+            .zip(&bases)
+            .filter(|(section, _)| !section.uninitialized && section.length > 0)
+            .map(|(section, base)| *base as u32 + section.length as u32)
+            .fold(0x0100, u32::max)
+            .min(0x10000);
+        (end - 0x0100) as u16
+    }
+
+    /// Defines a built-in symbol with the given numeric value, as a constant.
+    fn define_builtin(&mut self, builtin: Builtin, value: u16) {
+        let location = Location {
+            file_id: self.environment.main_file,
+            offset: 0,
+        };
+        let span = Span {
+            from: location,
+            to: location,
+        };
+        // The length of the program is only defined after the walk, so a label of the same name
+        // may have been defined by then.
+        if let Some((_, other_span)) = self
+            .label_definitions
+            .insert(builtin.identifier(), (value, span))
+        {
+            self.errors.push(Error::LabelDefinedMoreThanOnce {
+                name: builtin.identifier().to_string(),
+                span: other_span.into(),
+                other_span: span.into(),
+            });
+        }
+        self.constant_definitions.insert(builtin.identifier());
+    }
+
+    /// Returns the text of a built-in symbol in a raw word. The text of the length of the program
+    /// is only a placeholder of the right size, which gets replaced after the walk.
+    fn builtin_text(&mut self, builtin: Builtin, span: &Span) -> Vec<u8> {
+        match builtin {
+            Builtin::File => self.environment.file_names[span.from.file_id]
+                .clone()
+                .into_bytes(),
+            Builtin::Version => builtins::version_text().as_bytes().to_vec(),
+            Builtin::Date => builtins::date_text(self.environment.timestamp / 86_400).into_bytes(),
+            Builtin::Length => {
+                self.length_texts.push(self.statements.len());
+                b"0000".to_vec()
+            }
+        }
+    }
+
+    /// Walks a structured control-flow directive, lowering it to relative jumps to hidden labels.
+    fn walk_block_directive(&mut self, directive: Directive, span: Span) {
+        match directive {
+            Directive::When => {
+                self.push_zero_test(span);
+                let jump = self.jump_forward(true, span);
+                self.blocks.push(Block::When {
+                    span,
+                    jump,
+                    otherwise_span: None,
+                });
+            }
+            Directive::Otherwise => match self.blocks.last() {
+                Some(Block::When {
+                    otherwise_span: Some(other_span),
+                    ..
+                }) => self.errors.push(Error::BlockDirectiveDefinedMoreThanOnce {
+                    directive: "^otherwise".to_owned(),
+                    span: span.into(),
+                    other_span: (*other_span).into(),
+                }),
+                Some(Block::When { jump, .. }) => {
+                    let skipped = *jump;
+                    let jump = self.jump_forward(false, span);
+                    self.land(skipped, span);
+                    if let Some(Block::When {
+                        jump: block_jump,
+                        otherwise_span,
+                        ..
+                    }) = self.blocks.last_mut()
+                    {
+                        *block_jump = jump;
+                        *otherwise_span = Some(span);
+                    }
+                }
+                _ => self.errors.push(Error::NoMatchingBlock {
+                    directive: "^when".to_owned(),
+                    span: span.into(),
+                }),
+            },
+            Directive::End => match self.blocks.last() {
+                Some(Block::When { jump, .. }) => {
+                    let jump = *jump;
+                    self.blocks.pop();
+                    self.land(jump, span);
+                }
+                _ => self.errors.push(Error::NoMatchingBlock {
+                    directive: "^when".to_owned(),
+                    span: span.into(),
+                }),
+            },
+            Directive::While => {
+                let start = self.lambda_count;
+                self.lambda_count += 1;
+                self.blocks.push(Block::While {
+                    span,
+                    start,
+                    exit: None,
+                });
+                self.define_label(ScopedIdentifier::Lambda(start), span);
+            }
+            Directive::Do => match self.blocks.last() {
+                Some(Block::While {
+                    exit: Some((_, other_span)),
+                    ..
+                }) => self.errors.push(Error::BlockDirectiveDefinedMoreThanOnce {
+                    directive: "^do".to_owned(),
+                    span: span.into(),
+                    other_span: (*other_span).into(),
+                }),
+                Some(Block::While { .. }) => {
+                    self.push_zero_test(span);
+                    let jump = self.jump_forward(true, span);
+                    if let Some(Block::While { exit, .. }) = self.blocks.last_mut() {
+                        *exit = Some((jump, span));
+                    }
+                }
+                _ => self.errors.push(Error::NoMatchingBlock {
+                    directive: "^while".to_owned(),
+                    span: span.into(),
+                }),
+            },
+            Directive::Loop => match self.blocks.last() {
+                Some(Block::While { start, exit, .. }) => {
+                    let start = ScopedIdentifier::Lambda(*start);
+                    let exit = exit.map(|(jump, _)| jump);
+                    self.blocks.pop();
+                    // The jump out of the loop gets widened before the jump back to the start of
+                    // the loop is emitted, if a relative jump back would already put the end of
+                    // the loop too far for it, so that the jump back does not move afterwards.
+                    if let Some(exit) = exit {
+                        if !fits_relative(exit.address, self.pointer.wrapping_add(3)) {
+                            self.widen(exit, span);
+                        }
+                    }
+                    let long = match self.label_sections.get(&start) {
+                        Some(section) => *section != self.section,
+                        None => !self.sections[self.section].fixed,
+                    } || !fits_relative(self.pointer, self.label_definitions[&start].0);
+                    self.push_jump(start, false, long, span);
+                    if let Some(exit) = exit {
+                        self.land(exit, span);
+                    }
+                }
+                _ => self.errors.push(Error::NoMatchingBlock {
+                    directive: "^while".to_owned(),
+                    span: span.into(),
+                }),
+            },
+            _ => unreachable!(),
+        }
+        if self.blocks.is_empty() {
+            self.relocations.clear();
+        }
+    }
+
+    /// Pushes `#00 EQU`, which turns the condition on the stack into whether it is zero.
+    fn push_zero_test(&mut self, span: Span) {
+        self.statements
+            .push(Statement::LiteralHexByte(0x00).spanning(span));
+        self.push_bytes(2, span);
+        self.statements.push(
+            Statement::Instruction(instruction(InstructionKind::Equal, false)).spanning(span),
+        );
+        self.push_bytes(1, span);
+    }
+
+    /// Pushes a relative jump, or a conditional one, to a new hidden label that gets defined
+    /// later, with `land`.
+    fn jump_forward(&mut self, conditional: bool, span: Span) -> Jump {
+        let jump = Jump {
+            position: self.statements.len(),
+            address: self.pointer,
+            section: self.section,
+            relocations: self.relocations.len(),
+            target: self.lambda_count,
+        };
+        self.lambda_count += 1;
+        self.push_jump(
+            ScopedIdentifier::Lambda(jump.target),
+            conditional,
+            false,
+            span,
+        );
+        jump
+    }
+
+    /// Pushes the address of the `target` followed by a jump, either relative or absolute.
+    fn push_jump(&mut self, target: ScopedIdentifier, conditional: bool, long: bool, span: Span) {
+        let kind = if conditional {
+            InstructionKind::JumpCondition
+        } else {
+            InstructionKind::Jump
+        };
+        if long {
+            self.statements
+                .push(Statement::LiteralAbsoluteAddress(target.into()).spanning(span));
+            self.push_bytes(3, span);
+        } else {
+            self.statements
+                .push(Statement::LiteralRelativeAddress(target.into()).spanning(span));
+            self.push_bytes(2, span);
+        }
+        self.statements
+            .push(Statement::Instruction(instruction(kind, long)).spanning(span));
+        self.push_bytes(1, span);
+    }
+
+    /// Defines the hidden label that a forward jump goes to at the current address, widening the
+    /// jump first if the label is too far for a relative jump.
+    fn land(&mut self, jump: Jump, span: Span) {
+        if jump.section != self.section || !fits_relative(jump.address, self.pointer) {
+            self.widen(jump, span);
+        }
+        self.define_label(ScopedIdentifier::Lambda(jump.target), span);
+    }
+
+    /// Turns a relative jump into an absolute one, which is one byte longer, and moves everything
+    /// placed after the jump in the same section one byte forward, up to the next absolute pad or
+    /// the next pad that absorbs the growth. Reports an error if what moves runs into the next
+    /// absolute pad.
+    fn widen(&mut self, jump: Jump, span: Span) {
+        let reference = match &self.statements[jump.position].node {
+            Statement::LiteralRelativeAddress(reference) => reference.clone(),
+            _ => return,
+        };
+        self.statements[jump.position].node = Statement::LiteralAbsoluteAddress(reference);
+        if let Statement::Instruction(instruction) = &mut self.statements[jump.position + 1].node {
+            instruction.short = true;
+        }
+
+        // The number of bytes that the things placed after the current relocation move by.
+        let mut shift: u16 = 1;
+        for (section, relocation) in &mut self.relocations[jump.relocations..] {
+            if *section != jump.section {
+                continue;
+            }
+            match relocation {
+                Relocation::Label(scoped_identifier) => {
+                    if let Some((address, _)) = self.label_definitions.get_mut(scoped_identifier) {
+                        *address += shift;
+                    }
+                }
+                Relocation::NumericLabel(name, index) => {
+                    if let Some(definitions) = self.numeric_label_definitions.get_mut(name) {
+                        definitions[*index].1 += shift;
+                    }
+                }
+                Relocation::SectionSwitch(position) => {
+                    if let Statement::PadAbsolute(address) = &mut self.statements[*position].node {
+                        *address += shift;
+                    }
+                }
+                Relocation::AbsolutePad(pointer, address, pad_span) => {
+                    let previous_pointer = *pointer;
+                    *pointer = pointer.saturating_add(shift);
+                    if previous_pointer <= *address && *pointer > *address {
+                        self.errors.push(Error::PaddedBackwards {
+                            previous_pointer: *pointer as usize,
+                            desired_pointer: *address as usize,
+                            span: (*pad_span).into(),
+                        });
+                    }
+                    return;
+                }
+                Relocation::Alignment(position, alignment) => {
+                    if let Statement::PadRelative(padding) = &mut self.statements[*position].node {
+                        let alignment = *alignment;
+                        let aligned = (*padding + alignment - shift % alignment) % alignment;
+                        shift = shift + aligned - *padding;
+                        *padding = aligned;
+                    }
+                }
+                Relocation::ResolvedPad(position) => {
+                    if let Statement::PadRelative(padding) = &mut self.statements[*position].node {
+                        let absorbed = shift.min(*padding);
+                        *padding -= absorbed;
+                        shift -= absorbed;
+                    }
+                }
+            }
+            if shift == 0 {
+                return;
+            }
+        }
+        if jump.section == self.section {
+            self.increment_pointer(shift, span);
+            self.length += shift;
+        } else {
+            let section = &mut self.sections[jump.section];
+            section.pointer += shift;
+            section.length += shift;
+        }
+    }
+
+    /// Records something placed at the current address, if any structured block is open.
+    fn record(&mut self, relocation: Relocation) {
+        if !self.blocks.is_empty() {
+            self.relocations.push((self.section, relocation));
+        }
+    }
+
+    /// Puts the labels defined in the rest of the current file into a module.
+    fn walk_module(&mut self, span: Span, name: Option<&Word>) {
+        match name {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::MacroInvoke(name, arguments),
+                        ..
+                    },
+                ..
+            }) if arguments.is_empty() && !name.contains(&b':') => {
+                self.modules.insert(span.from.file_id, name.clone());
+            }
+            _ => self
+                .errors
+                .push(Error::ModuleNameExpected { span: span.into() }),
+        }
+    }
+
+    /// Returns the module of the file that a word at the given span appears in, if any.
+    fn namespace(&self, span: &Span) -> Option<Vec<u8>> {
+        self.modules.get(&span.from.file_id).cloned()
+    }
+
+    /// Qualifies the identifier of a label defined at the given span with the module of its
+    /// file, if there is one.
+    fn qualify(&self, scoped_identifier: ScopedIdentifier, span: &Span) -> ScopedIdentifier {
+        match self.modules.get(&span.from.file_id) {
+            Some(module) => scoped_identifier
+                .qualified(module)
+                .unwrap_or(scoped_identifier),
+            None => scoped_identifier,
+        }
+    }
+
+    /// Returns the id of the innermost macro expansion that is currently being walked, if any.
+    fn expansion(&self) -> Option<usize> {
+        self.expansions.last().copied().flatten()
+    }
+
+    /// Returns the identifier of a sublabel defined at the given depth in the current scope.
+    fn sublabel_identifier(
+        &self,
+        depth: usize,
+        name: &[u8],
+        span: &Span,
+    ) -> Result<ScopedIdentifier, Error> {
+        if self.scope.len() < depth {
+            return Err(Error::SublabelDefinedWithoutScope {
+                name: String::from_utf8_lossy(name).into_owned(),
+                span: (*span).into(),
+            });
+        }
+        let mut path = self.scope[..depth].to_vec();
+        path.push(name.to_vec());
+        Ok(ScopedIdentifier::from_path(&path))
+    }
+
+    /// Checks the condition of an assertion once all of the labels are defined, reporting the
+    /// message if the condition evaluates to zero.
+    fn walk_assertion(&mut self, symbols: &[Spanned<u8>], span: Span, message: Option<&Word>) {
+        let message = match message.and_then(message_text) {
+            Some((message, _)) => message,
+            None => {
+                self.errors
+                    .push(Error::DiagnosticMessageExpected { span: span.into() });
+                return;
+            }
+        };
+        let reference = Reference {
+            identifier: None,
+            expression: symbols.to_vec(),
+            scope: self.scope.clone(),
+            expansion: self.expansion(),
+            namespace: self.namespace(&span),
+        };
+        self.statements
+            .push(Statement::Assertion(reference, message).spanning(span));
+    }
+
+    /// Reports an error or a warning with a message from the source.
+    fn walk_diagnostic(&mut self, directive: Directive, span: Span, message: Option<&Word>) {
+        let (message, message_span) = match message.and_then(message_text) {
+            Some(message) => message,
+            None => {
+                self.errors
+                    .push(Error::DiagnosticMessageExpected { span: span.into() });
+                return;
+            }
+        };
+        let span = Span::combine(&span, &message_span).into();
+        match directive {
+            Directive::Warning => self.warnings.push(Warning::Custom { message, span }),
+            _ => self.errors.push(Error::Custom { message, span }),
+        }
+    }
+
+    /// Returns the identifier of a label named in a directive, along with the span of the name,
+    /// or `None` if the word is not a name. A name is a bare word, or a sublabel definition.
+    fn definition_identifier(
+        &self,
+        name: &Word,
+    ) -> Option<Result<(ScopedIdentifier, Span), Error>> {
+        match name {
+            Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::MacroInvoke(name, arguments),
+                        span,
+                    },
+                ..
+            } if arguments.is_empty() => Some(
+                scope_identifier(
+                    &bare_word_identifier(name),
+                    &self.scope,
+                    self.expansion(),
+                    span,
+                )
+                .map(|scoped_identifier| (self.qualify(scoped_identifier, span), *span)),
+            ),
+            Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::SublabelDefine(depth, name),
+                        span,
+                    },
+                ..
+            } => Some(
+                self.sublabel_identifier(*depth, name, span)
+                    .map(|scoped_identifier| (scoped_identifier, *span)),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Allocates `size` bytes of the zero page to a label. The allocations are placed at the end
+    /// of the walk, past the zero-page memory reserved in the fixed sections, so they never overlap
+    /// the variables declared by hand, wherever those are declared.
+    fn walk_allocation(&mut self, span: Span, name: Option<&Word>, size: Option<&Word>) {
+        let (scoped_identifier, name_span) =
+            match name.and_then(|name| self.definition_identifier(name)) {
+                Some(Ok(definition)) => definition,
+                Some(Err(err)) => {
+                    self.errors.push(err);
+                    return;
+                }
+                None => {
+                    self.errors
+                        .push(Error::AllocationNameExpected { span: span.into() });
+                    return;
+                }
+            };
+
+        let size = match size.and_then(hex_value) {
+            Some((size, _)) => size,
+            None => {
+                self.errors.push(Error::AllocationSizeExpected {
+                    span: name_span.into(),
+                });
+                return;
+            }
+        };
+
+        if let Some((_, other_span)) = self
+            .label_definitions
+            .insert(scoped_identifier.clone(), (0, name_span))
+        {
+            self.errors.push(Error::LabelDefinedMoreThanOnce {
+                name: scoped_identifier.to_string(),
+                span: name_span.into(),
+                other_span: other_span.into(),
+            });
+            return;
+        }
+        self.allocations
+            .push((scoped_identifier, size, Span::combine(&span, &name_span)));
+    }
+
+    /// Defines a constant, which is a label with an explicit value instead of an address.
+    fn walk_constant_definition(&mut self, span: Span, name: Option<&Word>, value: Option<&Word>) {
+        let (scoped_identifier, name_span) =
+            match name.and_then(|name| self.definition_identifier(name)) {
+                Some(Ok(definition)) => definition,
+                Some(Err(err)) => {
+                    self.errors.push(err);
+                    return;
+                }
+                None => {
+                    self.errors
+                        .push(Error::ConstantNameExpected { span: span.into() });
+                    return;
+                }
+            };
+
+        let value = match value.and_then(hex_value) {
+            Some((value, _)) => value,
+            None => {
+                self.errors.push(Error::ConstantValueExpected {
+                    span: name_span.into(),
+                });
+                return;
+            }
+        };
+
+        if let Some((_, other_span)) = self
+            .label_definitions
+            .insert(scoped_identifier.clone(), (value, name_span))
+        {
+            self.errors.push(Error::LabelDefinedMoreThanOnce {
+                name: scoped_identifier.to_string(),
+                span: name_span.into(),
+                other_span: other_span.into(),
+            });
+        }
+        self.constants
+            .insert(scoped_identifier.to_string().into_bytes(), value);
+        self.constant_definitions.insert(scoped_identifier);
+    }
+
+    /// Pads the pointer forward to the next multiple of the `alignment`, which must be a power of
+    /// two.
+    fn walk_alignment(&mut self, span: Span, alignment: Option<&Word>) {
+        let (alignment, alignment_span) = match alignment.and_then(hex_value) {
+            Some((alignment, alignment_span)) => (alignment, alignment_span),
+            None => {
+                self.errors
+                    .push(Error::AlignmentExpected { span: span.into() });
+                return;
+            }
+        };
+        if !alignment.is_power_of_two() {
+            self.errors.push(Error::AlignmentNotPowerOfTwo {
+                alignment,
+                span: alignment_span.into(),
+            });
+            return;
+        }
+
+        let section = &mut self.sections[self.section];
+        section.alignment = section.alignment.max(alignment);
+        let padding = (alignment - self.pointer % alignment) % alignment;
+        let span = Span::combine(&span, &alignment_span);
+        self.record(Relocation::Alignment(self.statements.len(), alignment));
+        self.statements
+            .push(Statement::PadRelative(padding).spanning(span));
+        self.increment_pointer(padding, span);
+    }
+
+    /// Fills the next `length` bytes of the program with the `value`.
+    fn walk_fill(&mut self, span: Span, length: Option<&Word>, value: Option<&Word>) {
+        let (length, length_span) = match length.and_then(hex_value) {
+            Some((length, length_span)) => (length, length_span),
+            None => {
+                self.errors
+                    .push(Error::FillLengthExpected { span: span.into() });
+                return;
+            }
+        };
+        let value = match value {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::RawHexByte(value),
+                        ..
+                    },
+                ..
+            }) => *value,
+            _ => {
+                self.errors.push(Error::FillValueExpected {
+                    span: length_span.into(),
+                });
+                return;
+            }
+        };
+
+        self.statements
+            .push(Statement::Fill(length, value).spanning(span));
+        self.push_bytes(length, span);
+    }
+
+    /// Evaluates the condition of a conditional assembly directive. Returns an `Err` if the
+    /// `argument` is not a valid condition for the directive.
+    fn evaluate_condition(
+        &mut self,
+        directive: Directive,
+        argument: Option<&Word>,
+    ) -> Result<bool, ()> {
+        let token = match argument {
+            Some(Word::Fine { token, .. }) => token,
+            Some(Word::Faulty {
+                errors: new_errors,
+                warnings: new_warnings,
+            }) => {
+                self.errors.extend(new_errors.iter().cloned());
+                self.warnings.extend(new_warnings.iter().cloned());
+                return Err(());
+            }
+            None => return Err(()),
+        };
+
+        match (directive, token) {
+            (
+                Directive::IfDefined,
+                Spanned {
+                    node: Token::MacroInvoke(name, arguments),
+                    ..
+                },
+            ) if arguments.is_empty() => Ok(self.is_defined(name)),
+            (
+                Directive::IfNotDefined,
+                Spanned {
+                    node: Token::MacroInvoke(name, arguments),
+                    ..
+                },
+            ) if arguments.is_empty() => Ok(!self.is_defined(name)),
+            (
+                Directive::If,
+                Spanned {
+                    node: Token::MacroInvoke(name, arguments),
+                    span,
+                },
+            ) if arguments.is_empty() => match self.constants.get(name) {
+                Some(value) => Ok(*value != 0),
+                None => {
+                    self.errors.push(Error::ConstantUndefined {
+                        name: String::from_utf8_lossy(name).into_owned(),
+                        span: (*span).into(),
+                    });
+                    Ok(false)
+                }
+            },
+            (
+                Directive::If,
+                Spanned {
+                    node: Token::RawHexByte(value),
+                    ..
+                },
+            ) => Ok(*value != 0),
+            (
+                Directive::If,
+                Spanned {
+                    node: Token::RawHexShort(value),
+                    ..
+                },
+            ) => Ok(*value != 0),
+            _ => Err(()),
+        }
+    }
+
+    /// Returns whether a macro or a constant with the given name has been defined.
+    fn is_defined(&self, name: &[u8]) -> bool {
+        self.macro_definitions.contains_key(name) || self.constants.contains_key(name)
+    }
+
+    /// Opens an anonymous block, which ends at the matching closing brace. The block is entered
+    /// through the `statement` referencing the end of the block, i.e. the block is skipped over
+    /// either unconditionally, conditionally, or with its address stashed on the return stack.
+    fn open_lambda(&mut self, statement: fn(Reference) -> Statement, span: Span) {
+        let id = self.lambda_count;
+        self.lambda_count += 1;
+        self.opened_braces.push(span);
+        self.opened_lambdas.push(Some(id));
+        self.statements
+            .push(statement(ScopedIdentifier::Lambda(id).into()).spanning(span));
+        self.push_bytes(4, span);
+    }
+
+    /// Expands the block that follows a repeat directive `count` times, substituting the value of
+    /// the iteration counter for the placeholders of its name, if the counter is named. The value
+    /// is written with two hexadecimal digits if the count is at most `100`, and with four digits
+    /// otherwise. Returns `None` if there is nothing to walk.
+    fn walk_repeat(
+        &mut self,
+        span: Span,
+        words: &mut Peekable<Iter<Rc<Word>>>,
+    ) -> Option<Vec<Rc<Word>>> {
+        let count = words.next().map(Rc::as_ref);
+        let counter = match words.peek().map(|word| word.as_ref()) {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::MacroInvoke(name, arguments),
+                        ..
+                    },
+                ..
+            }) if arguments.is_empty() => {
+                let name = name.clone();
+                words.next();
+                Some(name)
+            }
+            _ => None,
+        };
+        let body = self.walk_block(words);
+        if self.is_skipping() {
+            return None;
+        }
+
+        let (count, count_span) = match count.and_then(hex_value) {
+            Some((count, count_span)) => (count, count_span),
+            None => {
+                self.errors
+                    .push(Error::RepeatCountExpected { span: span.into() });
+                return None;
+            }
+        };
+        let body = match body {
+            Some(body) => body,
+            None => {
+                self.errors.push(Error::RepeatBodyExpected {
+                    span: Span::combine(&span, &count_span).into(),
+                });
+                return None;
+            }
+        };
+
+        let digits = if count <= 0x100 { 2 } else { 4 };
+        let mut items: Vec<Rc<Word>> = Vec::new();
+        for iteration in 0..count {
+            match &counter {
+                Some(name) => {
+                    let value: Vec<Spanned<u8>> = format!("{:01$x}", iteration, digits)
+                        .bytes()
+                        .map(|ch| ch.spanning(count_span))
+                        .collect();
+                    items.extend(body.iter().map(|item| {
+                        Rc::new(
+                            item.substitute(
+                                std::slice::from_ref(name),
+                                std::slice::from_ref(&value),
+                            ),
+                        )
+                    }));
+                }
+                None => items.extend(body.iter().cloned()),
+            }
+        }
+        Some(items)
+    }
+
+    /// Collects the words of the block enclosed in braces that starts at the next word, without
+    /// the outermost braces. Returns `None` if the next word does not open a block.
+    fn walk_block(&mut self, words: &mut Peekable<Iter<Rc<Word>>>) -> Option<Vec<Rc<Word>>> {
+        let mut items: Vec<Rc<Word>> = Vec::new();
+
+        match words.peek().map(|word| word.as_ref()) {
+            Some(Word::Fine {
+                token:
+                    Spanned {
+                        node: Token::OpeningBrace,
+                        span: opening_brace_span,
+                    },
+                warnings: new_warnings,
+            }) => {
+                let brace_level = self.opened_braces.len();
+                self.opened_braces.push(*opening_brace_span);
+                self.warnings.extend(new_warnings.iter().cloned());
+                words.next();
+                'block: loop {
+                    let option_word = words.next();
+                    match option_word.map(Rc::as_ref) {
+                        Some(Word::Fine {
+                            token:
+                                Spanned {
+                                    node: Token::OpeningBrace,
+                                    span,
+                                },
+                            warnings: new_warnings,
+                        })
+                        | Some(Word::Fine {
+                            token:
+                                Spanned {
+                                    node: Token::OpeningJumpBrace,
+                                    span,
+                                },
+                            warnings: new_warnings,
+                        })
+                        | Some(Word::Fine {
+                            token:
+                                Spanned {
+                                    node: Token::OpeningConditionalJumpBrace,
+                                    span,
+                                },
+                            warnings: new_warnings,
+                        }) => {
+                            self.opened_braces.push(*span);
+                            self.warnings.extend(new_warnings.iter().cloned());
+                            items.push(Rc::clone(option_word.unwrap()));
+                        }
+                        Some(Word::Fine {
+                            token:
+                                Spanned {
+                                    node: Token::ClosingBrace,
+                                    ..
+                                },
+                            warnings: new_warnings,
                         }) => {
                             self.opened_braces.pop().unwrap();
                             self.warnings.extend(new_warnings.iter().cloned());
                             if self.opened_braces.len() == brace_level {
-                                break 'macro_define;
+                                break 'block;
                             } else {
-                                items.push(option_word.unwrap().clone());
+                                items.push(Rc::clone(option_word.unwrap()));
                             }
                         }
-                        Some(word) => {
-                            items.push((*word).clone());
+                        Some(_) => {
+                            items.push(Rc::clone(option_word.unwrap()));
                         }
-                        None => break 'macro_define,
+                        None => break 'block,
                     }
                 }
+                Some(items)
             }
-            _ => (),
+            _ => None,
         }
+    }
 
-        if let Some((_, other_span)) = self.macro_definitions.insert(name.clone(), (items, span)) {
+    fn walk_macro_definition<'a>(
+        &mut self,
+        name: &Vec<u8>,
+        parameters: &[Vec<u8>],
+        span: Span,
+        mut words: Peekable<Iter<'a, Rc<Word>>>,
+    ) -> Peekable<Iter<'a, Rc<Word>>> {
+        let items = self.walk_block(&mut words).unwrap_or_default();
+
+        if let Some((_, _, other_span)) = self
+            .macro_definitions
+            .insert(name.clone(), (parameters.to_vec(), items, span))
+        {
             self.errors.push(Error::MacroDefinedMoreThanOnce {
                 name: String::from_utf8_lossy(&name).into_owned(),
                 span: span.into(),
@@ -463,23 +2248,423 @@ impl<'words> Walker<'words> {
 
 fn scope_identifier(
     identifier: &Identifier,
-    scope: &Option<Vec<u8>>,
+    scope: &[Vec<u8>],
+    expansion: Option<usize>,
     span: &Span,
 ) -> Result<ScopedIdentifier, Error> {
     match identifier {
         Identifier::Label(name) => Ok(ScopedIdentifier::Label(name.clone())),
-        Identifier::Path(label, sublabel) => {
-            Ok(ScopedIdentifier::Sublabel(label.clone(), sublabel.clone()))
-        }
-        Identifier::Sublabel(sublabel) => match scope {
-            Some(scope_name) => Ok(ScopedIdentifier::Sublabel(
-                scope_name.to_owned(),
-                sublabel.clone(),
-            )),
-            None => Err(Error::SublabelReferencedWithoutScope {
-                name: String::from_utf8_lossy(&sublabel).into_owned(),
+        Identifier::Path(label, sublabels) => {
+            Ok(ScopedIdentifier::Sublabel(label.clone(), sublabels.clone()))
+        }
+        Identifier::Sublabel(depth, sublabels) if scope.len() >= *depth => {
+            let mut path = scope[..*depth].to_vec();
+            path.extend(sublabels.iter().cloned());
+            Ok(ScopedIdentifier::from_path(&path))
+        }
+        Identifier::Sublabel(_, sublabels) => Err(Error::SublabelReferencedWithoutScope {
+            name: String::from_utf8_lossy(&sublabels.join(&b'/')).into_owned(),
+            span: (*span).into(),
+        }),
+        Identifier::Numeric(name, direction) => {
+            Ok(ScopedIdentifier::Numeric(name.clone(), *direction))
+        }
+        Identifier::Local(name) => match expansion {
+            Some(expansion) => Ok(ScopedIdentifier::Local(name.clone(), expansion)),
+            None => Err(Error::LocalLabelReferencedOutsideMacro {
+                name: String::from_utf8_lossy(name).into_owned(),
                 span: (*span).into(),
             }),
         },
     }
 }
+
+/// Scopes the identifier of an operand, if it has one, or keeps the symbols of the expression that
+/// the operand is otherwise.
+fn scope_operand(
+    operand: &Operand,
+    scope: &[Vec<u8>],
+    expansion: Option<usize>,
+    namespace: Option<Vec<u8>>,
+    span: &Span,
+) -> Result<Reference, Error> {
+    let identifier = match &operand.identifier {
+        Some(identifier) => Some(scope_identifier(identifier, scope, expansion, span)?),
+        None => None,
+    };
+    Ok(Reference {
+        identifier,
+        expression: operand.symbols.clone(),
+        scope: scope.to_vec(),
+        expansion,
+        namespace,
+    })
+}
+
+/// Turns an operand that can only be an expression into a reference that gets evaluated once all
+/// of the labels are defined.
+fn scope_expression(
+    operand: &Operand,
+    scope: &[Vec<u8>],
+    expansion: Option<usize>,
+    namespace: Option<Vec<u8>>,
+) -> Reference {
+    Reference {
+        identifier: None,
+        expression: operand.symbols.clone(),
+        scope: scope.to_vec(),
+        expansion,
+        namespace,
+    }
+}
+
+/// Returns the value of a raw hexadecimal byte or short, along with its span.
+fn hex_value(word: &Word) -> Option<(u16, Span)> {
+    match word {
+        Word::Fine {
+            token:
+                Spanned {
+                    node: Token::RawHexByte(value),
+                    span,
+                },
+            ..
+        } => Some((*value as u16, *span)),
+        Word::Fine {
+            token:
+                Spanned {
+                    node: Token::RawHexShort(value),
+                    span,
+                },
+            ..
+        } => Some((*value, *span)),
+        _ => None,
+    }
+}
+
+/// Returns whether a relative jump, whose address is pushed at `from`, can reach `to`.
+fn fits_relative(from: u16, to: u16) -> bool {
+    let offset = to as isize - from as isize - 3;
+    (-126..=126).contains(&offset)
+}
+
+fn instruction(instruction_kind: InstructionKind, short: bool) -> Instruction {
+    Instruction {
+        instruction_kind,
+        keep: false,
+        r#return: false,
+        short,
+    }
+}
+
+/// Returns the text of a quoted string or a raw word, along with its span.
+fn message_text(word: &Word) -> Option<(String, Span)> {
+    match word {
+        Word::Fine {
+            token:
+                Spanned {
+                    node: Token::RawWord(bytes),
+                    span,
+                },
+            ..
+        } => Some((String::from_utf8_lossy(bytes).into_owned(), *span)),
+        _ => None,
+    }
+}
+
+/// Interprets a bare word as a reference to a label or to a nested sublabel of a given label.
+fn bare_word_identifier(name: &[u8]) -> Identifier {
+    let mut path: Vec<Vec<u8>> = name.split(|c| *c == b'/').map(<[u8]>::to_vec).collect();
+    let label = path.remove(0);
+    if path.is_empty() {
+        Identifier::Label(label)
+    } else {
+        Identifier::Path(label, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assemble, Error, Warning};
+
+    fn errors(source: &str) -> Vec<Error> {
+        match assemble(source.as_bytes()) {
+            Ok((binary, _)) => panic!("expected errors, got {:02x?}", binary),
+            Err((errors, _)) => errors,
+        }
+    }
+
+    fn warnings(source: &str) -> Vec<Warning> {
+        match assemble(source.as_bytes()) {
+            Ok((_, warnings)) => warnings,
+            Err((errors, _)) => panic!("expected no errors, got {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn braces_not_matched() {
+        assert!(matches!(
+            errors("|0100 { #01")[..],
+            [Error::NoMatchingClosingBrace { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 #01 }")[..],
+            [Error::NoMatchingOpeningBrace { .. }]
+        ));
+    }
+
+    #[test]
+    fn macro_argument_count_mismatch() {
+        assert!(matches!(
+            errors("%add:a:b { #{a} #{b} ADD } |0100 add:01")[..],
+            [Error::MacroArgumentCountMismatch { .. }]
+        ));
+    }
+
+    #[test]
+    fn macro_parameter_undefined() {
+        assert!(matches!(
+            errors("%add:a { #{a} #{b} ADD } |0100 add:01")[..],
+            [Error::MacroParameterUndefined { .. }]
+        ));
+    }
+
+    #[test]
+    fn conditionals_not_matched() {
+        assert!(matches!(
+            errors("|0100 ^ifdef DEBUG #01")[..],
+            [Error::NoMatchingEndif { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 #01 ^endif")[..],
+            [Error::NoMatchingIf { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ^ifdef DEBUG ^else ^else ^endif")[..],
+            [Error::ElseDefinedMoreThanOnce { .. }]
+        ));
+    }
+
+    #[test]
+    fn condition_expected() {
+        assert!(matches!(
+            errors("|0100 ^ifdef #01 ^endif")[..],
+            [Error::ConditionExpected { .. }]
+        ));
+    }
+
+    #[test]
+    fn constant_undefined() {
+        assert!(matches!(
+            errors("|0100 ^if LEVEL #01 ^endif")[..],
+            [Error::ConstantUndefined { .. }]
+        ));
+    }
+
+    #[test]
+    fn constant_name_and_value_expected() {
+        assert!(matches!(
+            errors("^const #18")[..],
+            [Error::ConstantNameExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("^const write ADD")[..],
+            [Error::ConstantValueExpected { .. }]
+        ));
+    }
+
+    #[test]
+    fn constant_defined_more_than_once() {
+        assert!(matches!(
+            errors("^const WIDTH 0140 |0100 @WIDTH")[..],
+            [Error::LabelDefinedMoreThanOnce { .. }]
+        ));
+    }
+
+    #[test]
+    fn alignment() {
+        assert!(matches!(
+            errors("|0100 ^align page")[..],
+            [Error::AlignmentExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ^align 03")[..],
+            [Error::AlignmentNotPowerOfTwo {
+                alignment: 0x03,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn fill() {
+        assert!(matches!(
+            errors("|0100 ^fill")[..],
+            [Error::FillLengthExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ^fill 10 ADD")[..],
+            [Error::FillValueExpected { .. }]
+        ));
+    }
+
+    #[test]
+    fn nested_sublabel_without_scope() {
+        assert!(matches!(
+            errors("|0100 &&nested")[..],
+            [Error::SublabelDefinedWithoutScope { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ,&&nested")[..],
+            [Error::SublabelReferencedWithoutScope { .. }]
+        ));
+    }
+
+    #[test]
+    fn local_label_outside_macro() {
+        assert!(matches!(
+            errors("|0100 @%loop")[..],
+            [Error::LocalLabelDefinedOutsideMacro { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ,%loop")[..],
+            [Error::LocalLabelReferencedOutsideMacro { .. }]
+        ));
+    }
+
+    #[test]
+    fn pad_to_label() {
+        assert!(matches!(
+            errors("|label @label")[..],
+            [Error::PadForwardReference { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 @Main #01 #02 |Main")[..],
+            [Error::PaddedBackwards { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 |`ffff+2`")[..],
+            [Error::ExpressionOverflow { .. }]
+        ));
+    }
+
+    #[test]
+    fn pad_hex_digit_invalid() {
+        assert!(matches!(
+            errors("|01g0")[..],
+            [Error::HexDigitInvalid { digit: 'g', .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 $1g")[..],
+            [Error::HexDigitInvalid { digit: 'g', .. }]
+        ));
+    }
+
+    #[test]
+    fn sections() {
+        assert!(matches!(
+            errors("^section")[..],
+            [Error::SectionNameExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("^section data |8000")[..],
+            [Error::AbsolutePadInFloatingSection { .. }]
+        ));
+        assert!(matches!(
+            errors("^section bss #01")[..],
+            [Error::BytesInUninitializedSection { .. }]
+        ));
+    }
+
+    #[test]
+    fn zero_page_allocations() {
+        assert!(matches!(
+            errors("^zp 02")[..],
+            [Error::AllocationNameExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("^zp counter")[..],
+            [Error::AllocationSizeExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("^zp buffer 0100 ^zp counter 01")[..],
+            [Error::ZeroPageExhausted { .. }]
+        ));
+    }
+
+    #[test]
+    fn diagnostics() {
+        assert!(matches!(
+            errors("|0100 ^error \"unsupported\"")[..],
+            [Error::Custom { .. }]
+        ));
+        assert!(matches!(
+            errors("^error")[..],
+            [Error::DiagnosticMessageExpected { .. }]
+        ));
+        assert!(matches!(
+            warnings("|0100 ^warning \"deprecated\" #01")[..],
+            [Warning::Custom { .. }]
+        ));
+    }
+
+    #[test]
+    fn repeat() {
+        assert!(matches!(
+            errors("|0100 ^repeat 08 #00")[..],
+            [Error::RepeatBodyExpected { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 ^repeat { #00 }")[..],
+            [Error::RepeatCountExpected { .. }, ..]
+        ));
+    }
+
+    #[test]
+    fn blocks_not_matched() {
+        assert!(matches!(
+            errors("|0100 #01 ^when #02")[..],
+            [Error::NoMatchingBlockEnd { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 #01 ^end")[..],
+            [Error::NoMatchingBlock { .. }]
+        ));
+        assert!(matches!(
+            errors("|0100 #01 ^when ^otherwise ^otherwise ^end")[..],
+            [Error::BlockDirectiveDefinedMoreThanOnce { .. }]
+        ));
+    }
+
+    #[test]
+    fn widened_jump_overlaps_pad() {
+        assert!(matches!(
+            errors("|0100 #01 ^when ^repeat 7f { 01 } |0187 @pad 02 ^end BRK ;pad")[..],
+            [Error::PaddedBackwards { .. }]
+        ));
+    }
+
+    #[test]
+    fn module_name_expected() {
+        assert!(matches!(
+            errors("^module")[..],
+            [Error::ModuleNameExpected { .. }]
+        ));
+    }
+
+    #[test]
+    fn builtin_redefined() {
+        assert!(matches!(
+            errors("|0100 @ruxnasm &version")[..],
+            [Error::LabelDefinedMoreThanOnce { .. }, ..]
+        ));
+    }
+
+    #[test]
+    fn label_collides_when_trimmed() {
+        let source = format!("|0100 ;{0}a ;{0}b BRK @{0}a @{0}b", "x".repeat(64));
+        assert!(matches!(
+            warnings(&source)[..],
+            [Warning::LabelCollidesWhenTrimmed { .. }]
+        ));
+    }
+}